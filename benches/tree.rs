@@ -0,0 +1,54 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use plist::{Dictionary, Value};
+use plistoxide::{Document, Tree, expand_to_depth};
+
+/// A dictionary of `groups` arrays holding `items` dictionaries of four keys each, so about
+/// `groups * items * 5` nodes.
+fn document(groups: usize, items: usize) -> Value {
+    let item = |i: usize| {
+        Value::Dictionary(Dictionary::from_iter([
+            ("Name".to_owned(), Value::String(format!("Item {i}"))),
+            ("Enabled".to_owned(), Value::Boolean(i.is_multiple_of(2))),
+            ("Size".to_owned(), Value::Integer((i as u64).into())),
+            ("Payload".to_owned(), Value::Data(vec![0xAB; 16])),
+        ]))
+    };
+    Value::Dictionary(
+        (0..groups)
+            .map(|i| {
+                (
+                    format!("Group {i}"),
+                    Value::Array((0..items).map(item).collect()),
+                )
+            })
+            .collect(),
+    )
+}
+
+fn tree(c: &mut Criterion) {
+    let ctx = egui::Context::default();
+    let document = Document::new(document(100, 200));
+    expand_to_depth(&ctx, None, &[], document.root(), None);
+    let mut tree = Tree::default();
+    let mut selection = Default::default();
+
+    c.bench_function("rebuild 100k expanded nodes", |b| {
+        b.iter(|| {
+            tree.invalidate();
+            tree.refresh(&ctx, &document, None, &mut selection);
+        });
+    });
+    c.bench_function("refresh unchanged 100k expanded nodes", |b| {
+        b.iter(|| tree.refresh(&ctx, &document, None, &mut selection));
+    });
+    let last = ["Group 99", "199", "Payload"].map(String::from);
+    c.bench_function("find last of 100k rows", |b| {
+        b.iter(|| tree.row_index(&last));
+    });
+}
+
+criterion_group!(benches, tree);
+criterion_main!(benches);
@@ -9,28 +9,40 @@ use objc2::{MainThreadOnly, define_class, msg_send, rc::Retained, sel};
 use objc2_app_kit::{NSApplication, NSMenu, NSMenuItem};
 #[cfg(target_os = "macos")]
 use objc2_foundation::{MainThreadMarker, NSObject, NSObjectProtocol, ns_string};
-use plist::{Dictionary, Value};
+use plist::Value;
 use serde::{Deserialize, Serialize};
+
+use crate::{
+    changes::{self, Changes},
+    clipboard,
+    compare::Compare,
+    convert, data_file,
+    document::{Command, Document},
+    filter::Filter,
+    find::{self, Find},
+    goto::{self, GoTo},
+    history,
+    merge::Merge,
+    navigation, patch,
+    selection::Selection,
+    utils::try_pv,
+    widgets::{nested, tree::Tree},
+};
 #[cfg(target_os = "macos")]
 use std::{cell::SyncUnsafeCell, mem::MaybeUninit};
-use std::{
-    path::PathBuf,
-    sync::{Arc, Mutex, Once},
-};
+use std::{path::PathBuf, sync::Once};
 
 #[derive(Serialize, Deserialize)]
 pub struct PersistentState {
     path: Option<PathBuf>,
-    root: Arc<Mutex<Value>>,
-    unsaved: bool,
+    document: Document,
 }
 
 impl PersistentState {
     pub fn new(path: Option<PathBuf>) -> Self {
         Self {
             path,
-            root: Arc::new(Mutex::new(Value::Dictionary(Dictionary::new()))),
-            unsaved: false,
+            document: Document::default(),
         }
     }
 }
@@ -41,6 +53,18 @@ pub struct PlistOxide {
     error: Option<String>,
     closing: bool,
     can_close: bool,
+    selection: Selection,
+    find: Find,
+    filter: Filter,
+    goto: GoTo,
+    compare: Compare,
+    changes: Changes,
+    /// The three-way merge being resolved in the open document.
+    merge: Option<Merge>,
+    tree: Tree,
+    /// A row to scroll to once it has been laid out, and where to bring it. `None` scrolls just
+    /// enough to show it.
+    scroll_to: Option<(Vec<String>, Option<egui::Align>)>,
     #[cfg(target_os = "macos")]
     _menu: Retained<PlistOxideMenu>,
 }
@@ -55,6 +79,18 @@ static OPENING_FILE: std::sync::Mutex<bool> = std::sync::Mutex::new(false);
 #[cfg(target_os = "macos")]
 static SAVING_FILE: std::sync::Mutex<bool> = std::sync::Mutex::new(false);
 
+#[cfg(target_os = "macos")]
+static COMPARING_FILES: std::sync::Mutex<bool> = std::sync::Mutex::new(false);
+
+#[cfg(target_os = "macos")]
+static MERGING_FILES: std::sync::Mutex<bool> = std::sync::Mutex::new(false);
+
+#[cfg(target_os = "macos")]
+static EXPORTING_PATCH: std::sync::Mutex<bool> = std::sync::Mutex::new(false);
+
+#[cfg(target_os = "macos")]
+static APPLYING_PATCH: std::sync::Mutex<bool> = std::sync::Mutex::new(false);
+
 #[cfg(target_os = "macos")]
 define_class!(
     #[unsafe(super(NSObject))]
@@ -75,6 +111,30 @@ define_class!(
             *SAVING_FILE.lock().unwrap() = true;
             unsafe { (*EGUI_CTX.get()).assume_init_mut().request_repaint() };
         }
+
+        #[unsafe(method(comparingFiles))]
+        fn comparing_files(&self) {
+            *COMPARING_FILES.lock().unwrap() = true;
+            unsafe { (*EGUI_CTX.get()).assume_init_mut().request_repaint() };
+        }
+
+        #[unsafe(method(mergingFiles))]
+        fn merging_files(&self) {
+            *MERGING_FILES.lock().unwrap() = true;
+            unsafe { (*EGUI_CTX.get()).assume_init_mut().request_repaint() };
+        }
+
+        #[unsafe(method(exportingPatch))]
+        fn exporting_patch(&self) {
+            *EXPORTING_PATCH.lock().unwrap() = true;
+            unsafe { (*EGUI_CTX.get()).assume_init_mut().request_repaint() };
+        }
+
+        #[unsafe(method(applyingPatch))]
+        fn applying_patch(&self) {
+            *APPLYING_PATCH.lock().unwrap() = true;
+            unsafe { (*EGUI_CTX.get()).assume_init_mut().request_repaint() };
+        }
     }
 );
 
@@ -89,6 +149,26 @@ impl PlistOxide {
         *SAVING_FILE.lock().unwrap() = false;
     }
 
+    #[cfg(target_os = "macos")]
+    fn comparing_files_false() {
+        *COMPARING_FILES.lock().unwrap() = false;
+    }
+
+    #[cfg(target_os = "macos")]
+    fn merging_files_false() {
+        *MERGING_FILES.lock().unwrap() = false;
+    }
+
+    #[cfg(target_os = "macos")]
+    fn exporting_patch_false() {
+        *EXPORTING_PATCH.lock().unwrap() = false;
+    }
+
+    #[cfg(target_os = "macos")]
+    fn applying_patch_false() {
+        *APPLYING_PATCH.lock().unwrap() = false;
+    }
+
     #[cfg(target_os = "macos")]
     fn new_global_menu(cc: &eframe::CreationContext<'_>) -> Retained<PlistOxideMenu> {
         unsafe { (*EGUI_CTX.get()).write(cc.egui_ctx.clone()) };
@@ -121,6 +201,54 @@ impl PlistOxide {
         unsafe { file_save.setTarget(Some(&menu)) };
         file_menu.addItem(&file_save);
 
+        file_menu.addItem(&NSMenuItem::separatorItem(mtm));
+
+        let file_compare = unsafe {
+            NSMenuItem::initWithTitle_action_keyEquivalent(
+                NSMenuItem::alloc(mtm),
+                ns_string!("Compare..."),
+                Some(sel!(comparingFiles)),
+                ns_string!(""),
+            )
+        };
+        unsafe { file_compare.setTarget(Some(&menu)) };
+        file_menu.addItem(&file_compare);
+
+        let file_merge = unsafe {
+            NSMenuItem::initWithTitle_action_keyEquivalent(
+                NSMenuItem::alloc(mtm),
+                ns_string!("Merge..."),
+                Some(sel!(mergingFiles)),
+                ns_string!(""),
+            )
+        };
+        unsafe { file_merge.setTarget(Some(&menu)) };
+        file_menu.addItem(&file_merge);
+
+        file_menu.addItem(&NSMenuItem::separatorItem(mtm));
+
+        let file_export_patch = unsafe {
+            NSMenuItem::initWithTitle_action_keyEquivalent(
+                NSMenuItem::alloc(mtm),
+                ns_string!("Export Changes as Patch..."),
+                Some(sel!(exportingPatch)),
+                ns_string!(""),
+            )
+        };
+        unsafe { file_export_patch.setTarget(Some(&menu)) };
+        file_menu.addItem(&file_export_patch);
+
+        let file_apply_patch = unsafe {
+            NSMenuItem::initWithTitle_action_keyEquivalent(
+                NSMenuItem::alloc(mtm),
+                ns_string!("Apply Patch..."),
+                Some(sel!(applyingPatch)),
+                ns_string!(""),
+            )
+        };
+        unsafe { file_apply_patch.setTarget(Some(&menu)) };
+        file_menu.addItem(&file_apply_patch);
+
         let file_item = NSMenuItem::new(mtm);
         file_item.setSubmenu(Some(&file_menu));
         NSApplication::sharedApplication(mtm)
@@ -145,11 +273,33 @@ impl PlistOxide {
             error: None,
             can_close: false,
             closing: false,
+            selection: Selection::default(),
+            find: Find::default(),
+            filter: Filter::default(),
+            goto: GoTo::default(),
+            compare: Compare::default(),
+            changes: Changes::default(),
+            merge: None,
+            tree: Tree::default(),
+            scroll_to: None,
             #[cfg(target_os = "macos")]
             _menu: menu,
         }
     }
 
+    /// Starts on the result of a merge run from the command line, to be written to its output.
+    #[must_use]
+    pub fn merging(cc: &eframe::CreationContext<'_>, root: Value, merge: Merge) -> Self {
+        let mut ret = Self::new(cc, merge.output().map(PathBuf::from));
+        ret.state.path = merge.output().map(PathBuf::from);
+        // The output holds whatever git left there, not something to open.
+        ret.open_file.call_once(|| {});
+        crate::widgets::display_key::set_document(&cc.egui_ctx, ret.state.path.as_deref());
+        ret.state.document.replace(root);
+        ret.merge = Some(merge);
+        ret
+    }
+
     fn handle_error(&mut self, ctx: &egui::Context, action: &str) {
         let Some(error) = self.error.as_ref() else {
             return;
@@ -182,6 +332,48 @@ impl PlistOxide {
         }
     }
 
+    /// Merges three files picked by the user into a new document.
+    fn merge_files(&mut self, ctx: &egui::Context) {
+        let pick = |title: &str| rfd::FileDialog::new().set_title(title).pick_file();
+        let Some(base) = pick("Choose Base") else {
+            return;
+        };
+        let Some(local) = pick("Choose Local") else {
+            return;
+        };
+        let Some(remote) = pick("Choose Remote") else {
+            return;
+        };
+        match Merge::load(&base, &local, &remote, None) {
+            Ok((root, merge)) => {
+                self.state.path = None;
+                crate::widgets::display_key::set_document(ctx, None);
+                self.state.document.replace(root);
+                self.selection.clear();
+                self.merge = Some(merge);
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// Saves the edits made since the file was opened or last saved, as a patch.
+    fn export_patch(&self, ctx: &egui::Context) {
+        patch::save(
+            ctx,
+            self.state.document.baseline(),
+            self.state.document.root(),
+        );
+    }
+
+    fn apply_patch(&mut self, ctx: &egui::Context) {
+        let command = patch::load(ctx, self.state.document.root());
+        if command.is_some() {
+            self.apply(ctx, command);
+            self.state.document.commit();
+            self.selection.clear();
+        }
+    }
+
     fn update_title(&self, ctx: &egui::Context) {
         ctx.send_viewport_cmd(ViewportCommand::Title(format!(
             "{}{}",
@@ -190,7 +382,11 @@ impl PlistOxide {
                 .as_ref()
                 .and_then(|v| v.to_str())
                 .unwrap_or("Untitled.plist"),
-            if self.state.unsaved { " *" } else { "" }
+            if self.state.document.is_unsaved() {
+                " *"
+            } else {
+                ""
+            }
         )));
     }
 
@@ -204,23 +400,217 @@ impl PlistOxide {
         let Some(path) = &self.state.path else {
             return;
         };
-        self.error = plist::to_file_xml(path, &self.state.root)
-            .err()
-            .map(|v| v.to_string());
-        self.state.unsaved = self.error.is_some();
+        self.error = match &self.merge {
+            Some(merge) if merge.output() == Some(path.as_path()) => {
+                merge.save(self.state.document.root()).err()
+            }
+            _ => plist::to_file_xml(path, self.state.document.root())
+                .err()
+                .map(|v| v.to_string()),
+        };
+        if self.error.is_none() {
+            self.state.document.mark_saved();
+        }
         self.handle_error(ctx, "saving");
         self.update_title(ctx);
     }
+
+    fn handle_clipboard(&mut self, ctx: &egui::Context) {
+        if ctx.memory(|v| v.focused().is_some()) {
+            return;
+        }
+        let (events, shift) = ctx.input(|i| {
+            (
+                i.events
+                    .iter()
+                    .filter(|v| {
+                        matches!(
+                            v,
+                            egui::Event::Copy | egui::Event::Cut | egui::Event::Paste(_)
+                        )
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>(),
+                i.modifiers.shift,
+            )
+        });
+        for event in events {
+            let command = Self::handle_clipboard_event(
+                ctx,
+                event,
+                shift,
+                &mut self.selection,
+                self.state.document.root(),
+            );
+            self.apply(ctx, command);
+        }
+    }
+
+    fn handle_clipboard_event(
+        ctx: &egui::Context,
+        event: egui::Event,
+        shift: bool,
+        selection: &mut Selection,
+        root: &Value,
+    ) -> Option<Command> {
+        match event {
+            egui::Event::Copy | egui::Event::Cut => {
+                let cut = matches!(event, egui::Event::Cut);
+                clipboard::copy(ctx, &selection.roots(root), root, cut)
+                    .inspect(|_| selection.clear())
+            }
+            egui::Event::Paste(text) => {
+                // Pastes requested from a menu may target a nested document instead of the root.
+                let (nesting, path, as_child) = clipboard::take_pending_paste(ctx)
+                    .or_else(|| Some((Vec::new(), selection.last(root)?, shift)))?;
+                let items = clipboard::from_text(&text);
+                if items.is_empty() {
+                    return None;
+                }
+                let command = nested::read(ctx, root, &nesting, |p| {
+                    clipboard::paste(&path, as_child, items, p)
+                })??;
+                Some(Command::nested(&nesting, command))
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_conversion(&mut self, ctx: &egui::Context) {
+        let Some((nesting, pending)) = convert::pending(ctx) else {
+            return;
+        };
+        let mut choice = None;
+        let mut cancelled = false;
+        egui::Modal::new(egui::Id::new("ConversionModal")).show(ctx, |ui| {
+            ui.heading(format!(
+                "Convert to {}",
+                pending.to.map_or_else(String::new, |v| format!("{v:?}"))
+            ));
+            ui.separator();
+            let options = match pending.targets.as_slice() {
+                [(_, options)] => {
+                    for v in options {
+                        ui.label(format!("{}: {}", v.label, v.loss.unwrap_or("No loss")));
+                    }
+                    options.iter().map(|v| v.label).collect()
+                }
+                targets => {
+                    let lossy = targets.iter().filter(|(_, v)| v[0].loss.is_some()).count();
+                    ui.label(format!(
+                        "{lossy} of {} values cannot be converted without losing data.",
+                        targets.len()
+                    ));
+                    vec!["Convert"]
+                }
+            };
+            ui.separator();
+            egui::Sides::new().show(
+                ui,
+                |_| {},
+                |ui| {
+                    if ui.button("Cancel").clicked() {
+                        cancelled = true;
+                    }
+                    for (i, label) in options.into_iter().enumerate().rev() {
+                        if ui.button(label).clicked() {
+                            choice = Some(i);
+                        }
+                    }
+                },
+            );
+        });
+        if let Some(choice) = choice {
+            convert::cancel(ctx);
+            let command = pending.apply(choice).map(|v| Command::nested(&nesting, v));
+            self.selection.clear();
+            self.apply(ctx, command);
+        } else if cancelled {
+            convert::cancel(ctx);
+        }
+    }
+
+    /// Expands or collapses the selected rows recursively, unless a text field has the keyboard.
+    fn handle_expansion(&self, ctx: &egui::Context) {
+        if ctx.memory(|v| v.focused().is_some()) {
+            return;
+        }
+        let depth = if ctx
+            .input_mut(|v| v.consume_shortcut(&crate::widgets::entry::EXPAND_SHORTCUT))
+        {
+            None
+        } else if ctx.input_mut(|v| v.consume_shortcut(&crate::widgets::entry::COLLAPSE_SHORTCUT)) {
+            Some(0)
+        } else {
+            return;
+        };
+        let root = self.state.document.root();
+        for path in self.selection.roots(root) {
+            crate::widgets::entry::expand_to_depth(ctx, None, &path, root, depth);
+        }
+    }
+
+    fn handle_navigation(&mut self, ctx: &egui::Context) {
+        let response = navigation::handle(ctx, &mut self.selection, self.state.document.root());
+        self.apply(ctx, response.command);
+        if let Some(path) = response.scroll_to {
+            self.scroll_to = Some((path, None));
+        }
+    }
+
+    /// Applies an edit made this frame. Whatever depends on the document notices its revision
+    /// change and catches up.
+    fn apply(&mut self, ctx: &egui::Context, command: Option<Command>) {
+        if let Some(command) = command
+            && self.state.document.apply(command)
+        {
+            ctx.request_repaint();
+        }
+    }
+
+    /// Selects the row at `path`, expanding its ancestors and scrolling it into view.
+    fn reveal(&mut self, ctx: &egui::Context, path: Vec<String>) {
+        crate::widgets::entry::reveal(ctx, &path);
+        self.selection.set(path.clone());
+        self.scroll_to = Some((path, Some(egui::Align::Center)));
+    }
+
+    /// Closes the undo step once the pointer is released and no text field has focus, so that a
+    /// drag or a run of typing ends up as a single step.
+    fn commit(&mut self, ctx: &egui::Context) {
+        let typing = ctx
+            .memory(|v| v.focused())
+            .is_some_and(|id| egui::text_edit::TextEditState::load(ctx, id).is_some());
+        if !ctx.input(|i| i.pointer.any_down()) && !typing {
+            self.state.document.commit();
+        }
+    }
+
+    fn undo(&mut self) {
+        if self.state.document.undo() {
+            self.selection.clear();
+        }
+    }
+
+    fn redo(&mut self) {
+        if self.state.document.redo() {
+            self.selection.clear();
+        }
+    }
 }
 
 impl eframe::App for PlistOxide {
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        // A merge run for git shouldn't replace the session the app reopens.
+        if self.merge.as_ref().is_some_and(|v| v.output().is_some()) {
+            return;
+        }
         eframe::set_value(storage, eframe::APP_KEY, &self.state);
     }
 
     fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
         if ui.ctx().input(|i| i.viewport().close_requested())
-            && self.state.unsaved
+            && (self.state.document.is_unsaved() || self.compare.is_unsaved())
             && !self.can_close
         {
             self.closing = true;
@@ -250,13 +640,15 @@ impl eframe::App for PlistOxide {
         }
 
         self.open_file.call_once(|| {
+            crate::widgets::display_key::set_document(ui.ctx(), self.state.path.as_deref());
+            self.merge = None;
             let Some(path) = &self.state.path else {
                 return;
             };
             if !path.exists() || !path.is_file() {
                 return;
             }
-            self.state.root = match plist::from_file(path) {
+            let root = match plist::from_file(path) {
                 Ok(v) => {
                     ui.ctx().send_viewport_cmd(ViewportCommand::Title(
                         self.state
@@ -271,9 +663,11 @@ impl eframe::App for PlistOxide {
                 }
                 Err(e) => {
                     self.error = Some(e.to_string());
-                    Mutex::new(Value::Dictionary(plist::Dictionary::default())).into()
+                    Value::Dictionary(plist::Dictionary::default())
                 }
             };
+            self.state.document.replace(root);
+            self.selection.clear();
         });
 
         self.handle_error(ui.ctx(), "opening");
@@ -311,6 +705,89 @@ impl eframe::App for PlistOxide {
                             self.save_file(ui.ctx());
                             ui.close();
                         }
+
+                        ui.separator();
+                        if ui.button("Compare…").clicked() {
+                            self.compare.open();
+                            ui.close();
+                        }
+                        if ui.button("Merge…").clicked() {
+                            self.merge_files(ui.ctx());
+                            ui.close();
+                        }
+
+                        ui.separator();
+                        if ui.button("Export Changes as Patch…").clicked() {
+                            self.export_patch(ui.ctx());
+                            ui.close();
+                        }
+                        if ui.button("Apply Patch…").clicked() {
+                            self.apply_patch(ui.ctx());
+                            ui.close();
+                        }
+                    });
+
+                    ui.menu_button("Edit", |ui| {
+                        if ui
+                            .add_enabled(
+                                self.state.document.can_undo(),
+                                egui::Button::new("Undo").shortcut_text(
+                                    ui.ctx().format_shortcut(&history::UNDO_SHORTCUT),
+                                ),
+                            )
+                            .clicked()
+                        {
+                            self.undo();
+                            ui.close();
+                        }
+
+                        if ui
+                            .add_enabled(
+                                self.state.document.can_redo(),
+                                egui::Button::new("Redo").shortcut_text(
+                                    ui.ctx().format_shortcut(&history::REDO_SHORTCUT),
+                                ),
+                            )
+                            .clicked()
+                        {
+                            self.redo();
+                            ui.close();
+                        }
+
+                        ui.separator();
+                        if ui
+                            .add(
+                                egui::Button::new("Find…")
+                                    .shortcut_text(ui.ctx().format_shortcut(&find::FIND_SHORTCUT)),
+                            )
+                            .clicked()
+                        {
+                            self.find.open();
+                            ui.close();
+                        }
+                        if ui
+                            .add(
+                                egui::Button::new("Go to Key Path…")
+                                    .shortcut_text(ui.ctx().format_shortcut(&goto::GOTO_SHORTCUT)),
+                            )
+                            .clicked()
+                        {
+                            self.goto.open();
+                            ui.close();
+                        }
+
+                        ui.separator();
+                        if ui
+                            .add(
+                                egui::Button::new("Review Changes…").shortcut_text(
+                                    ui.ctx().format_shortcut(&changes::REVIEW_SHORTCUT),
+                                ),
+                            )
+                            .clicked()
+                        {
+                            self.changes.open();
+                            ui.close();
+                        }
                     });
                 });
             });
@@ -336,9 +813,160 @@ impl eframe::App for PlistOxide {
             Self::saving_file_false();
         }
 
+        #[cfg(target_os = "macos")]
+        if *COMPARING_FILES.lock().unwrap() {
+            self.compare.open();
+            Self::comparing_files_false();
+        }
+
+        #[cfg(target_os = "macos")]
+        if *MERGING_FILES.lock().unwrap() {
+            self.merge_files(ui.ctx());
+            Self::merging_files_false();
+        }
+
+        #[cfg(target_os = "macos")]
+        if *EXPORTING_PATCH.lock().unwrap() {
+            self.export_patch(ui.ctx());
+            Self::exporting_patch_false();
+        }
+
+        #[cfg(target_os = "macos")]
+        if *APPLYING_PATCH.lock().unwrap() {
+            self.apply_patch(ui.ctx());
+            Self::applying_patch_false();
+        }
+
+        if ui.ctx().memory(|v| v.focused().is_none()) {
+            if ui
+                .ctx()
+                .input_mut(|v| v.consume_shortcut(&history::REDO_SHORTCUT))
+            {
+                self.redo();
+            } else if ui
+                .ctx()
+                .input_mut(|v| v.consume_shortcut(&history::UNDO_SHORTCUT))
+            {
+                self.undo();
+            }
+        }
+
+        if ui
+            .ctx()
+            .input_mut(|v| v.consume_shortcut(&find::FIND_SHORTCUT))
+        {
+            self.find.open();
+        }
+        if self.find.is_open() {
+            let response = egui::Panel::top("find_bar")
+                .show_inside(ui, |ui| self.find.ui(ui, &mut self.state.document))
+                .inner;
+            if let Some(path) = response.reveal {
+                self.reveal(ui.ctx(), path);
+            }
+        }
+
+        self.handle_expansion(ui.ctx());
+        self.handle_navigation(ui.ctx());
+
+        if ui
+            .ctx()
+            .input_mut(|v| v.consume_shortcut(&goto::GOTO_SHORTCUT))
+        {
+            self.goto.open();
+        }
+        if self.goto.is_open()
+            && let Some(path) = egui::Panel::top("goto_bar")
+                .show_inside(ui, |ui| self.goto.ui(ui, self.state.document.root()))
+                .inner
+        {
+            self.reveal(ui.ctx(), path);
+        }
+
+        if egui::Panel::top("filter_bar")
+            .show_inside(ui, |ui| self.filter.ui(ui, &self.state.document))
+            .inner
+        {
+            self.tree.invalidate();
+        }
+
+        self.handle_clipboard(ui.ctx());
+        self.handle_conversion(ui.ctx());
+        data_file::show_report(ui.ctx());
+        patch::show_report(ui.ctx());
+        let root = self.state.document.root();
+        let commands = [
+            crate::widgets::hex_editor::show(ui.ctx(), root),
+            crate::widgets::multiline::show(ui.ctx(), root),
+            crate::widgets::table_view::show(ui.ctx(), root),
+        ];
+        for command in commands {
+            self.apply(ui.ctx(), command);
+        }
+        self.compare.show(ui.ctx());
+
+        if ui
+            .ctx()
+            .input_mut(|v| v.consume_shortcut(&changes::REVIEW_SHORTCUT))
+        {
+            self.changes.open();
+        }
+        self.changes.refresh(ui.ctx(), &self.state.document);
+        let response = self.changes.show(ui.ctx(), &self.state.document);
+        if response.command.is_some() {
+            self.apply(ui.ctx(), response.command);
+            self.state.document.commit();
+        }
+        if let Some(path) = response.reveal {
+            self.reveal(ui.ctx(), path);
+        }
+        if response.save {
+            self.save_file(ui.ctx());
+        }
+
+        if let Some(merge) = &mut self.merge {
+            let response = egui::Panel::right("merge_panel")
+                .resizable(true)
+                .show_inside(ui, |ui| merge.ui(ui, &mut self.state.document))
+                .inner;
+            if let Some(path) = response.reveal {
+                self.reveal(ui.ctx(), path);
+            }
+            if response.quit {
+                self.can_close = true;
+                ui.ctx().send_viewport_cmd(ViewportCommand::Close);
+            }
+        }
+
+        let selected = self.selection.last(self.state.document.root());
+        if let Some(path) = egui::Panel::bottom("breadcrumb")
+            .show_inside(ui, |ui| goto::breadcrumb(ui, selected.as_deref()))
+            .inner
+        {
+            self.reveal(ui.ctx(), path);
+        }
+
         egui::CentralPanel::default().show_inside(ui, |ui| {
-            TableBuilder::new(ui)
+            self.tree.refresh(
+                ui.ctx(),
+                &self.state.document,
+                self.filter.visible(),
+                &mut self.selection,
+            );
+            let mut table = TableBuilder::new(ui);
+            if let Some((path, align)) = &self.scroll_to {
+                if let Some(row) = self.tree.row_index(path) {
+                    table = table.scroll_to_row(row, *align);
+                    self.scroll_to = None;
+                } else if try_pv(path, self.state.document.root()).is_none()
+                    || self.filter.visible().is_some_and(|v| !v.contains(path))
+                {
+                    self.scroll_to = None;
+                }
+            }
+            table
                 .striped(true)
+                .sense(egui::Sense::click())
                 .resizable(true)
                 .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
                 .column(Column::remainder())
@@ -357,12 +985,15 @@ impl eframe::App for PlistOxide {
                     });
                 })
                 .body(|mut body| {
-                    let state =
-                        crate::widgets::entry::PlistEntry::new(self.state.root.clone(), vec![])
-                            .show(&mut body);
-                    self.state.unsaved |= state != crate::widgets::entry::ChangeState::Unchanged;
-                    self.update_title(body.ui_mut().ctx());
+                    let ctx = body.ui_mut().ctx().clone();
+                    let commands =
+                        self.tree
+                            .show(body, self.state.document.root(), &mut self.selection);
+                    self.apply(&ctx, Command::batch(commands));
+                    self.update_title(&ctx);
                 });
         });
+
+        self.commit(ui.ctx());
     }
 }
@@ -0,0 +1,324 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::{collections::HashMap, sync::Arc};
+
+use egui::{
+    Align, Context, Id, Key, KeyboardShortcut, Label, Layout, Modifiers, RichText, Sense, Ui, vec2,
+};
+use egui_extras::{Column, TableBuilder};
+use plist::Value;
+
+use crate::{
+    compare::{colour, summary},
+    diff::{self, Change, Row, Side},
+    document::{Command, Document},
+    utils::try_pv,
+};
+
+pub const REVIEW_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::R);
+
+const ROW_HEIGHT: f32 = 20.0;
+const GUTTER_WIDTH: f32 = 3.0;
+
+/// How a row of the open document differs from the saved file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Mark {
+    /// How the node itself differs, if it does.
+    pub change: Option<Change>,
+    /// How many of its children were removed, or moved somewhere else.
+    pub removed: usize,
+    /// Whether it can be reverted by itself, rather than along with the node it came with.
+    pub revertible: bool,
+}
+
+impl Mark {
+    #[must_use]
+    pub fn describe(&self) -> String {
+        let mut ret = self
+            .change
+            .map(|v| v.label().to_owned())
+            .unwrap_or_default();
+        if self.removed > 0 {
+            if !ret.is_empty() {
+                ret.push_str(", ");
+            }
+            let s = if self.removed == 1 { "" } else { "ren" };
+            ret.push_str(&format!("{} child{s} removed", self.removed));
+        }
+        ret
+    }
+}
+
+/// The differences between the saved file and the open document at one revision.
+struct Snapshot {
+    baseline: Arc<Value>,
+    rows: Vec<Row>,
+    /// The marks of the rows in the open document, by path.
+    marks: HashMap<Vec<String>, Mark>,
+}
+
+fn snapshot_id() -> Id {
+    Id::new("ChangeMarks")
+}
+
+fn snapshot(ctx: &Context) -> Option<Arc<Snapshot>> {
+    ctx.data_mut(|d| d.get_temp::<Arc<Snapshot>>(snapshot_id()))
+}
+
+fn marks(rows: &[Row]) -> HashMap<Vec<String>, Mark> {
+    let mut ret = HashMap::<_, Mark>::new();
+    for row in rows {
+        if row.present[1] {
+            let mark = ret.entry(row.paths[1].clone()).or_default();
+            mark.change = Some(row.change);
+            mark.revertible = row.copyable();
+        } else if !row.inherited
+            && let Some((_, parent)) = row.paths[1].split_last()
+        {
+            ret.entry(parent.to_vec()).or_default().removed += 1;
+        }
+    }
+    ret
+}
+
+/// How the row at `path` in the open document differs from the saved file, if it does.
+#[must_use]
+pub fn mark(ctx: &Context, path: &[String]) -> Option<Mark> {
+    snapshot(ctx)?.marks.get(path).copied()
+}
+
+/// The edit that puts the node at `path` back the way it was saved.
+#[must_use]
+pub fn revert(ctx: &Context, path: &[String], root: &Value) -> Option<Command> {
+    let snapshot = snapshot(ctx)?;
+    let row = snapshot
+        .rows
+        .iter()
+        .find(|v| v.present[1] && v.paths[1] == path && v.copyable())?;
+    diff::copy(row, Side::Right, [&snapshot.baseline, root])
+}
+
+/// Paints the change mark of the row at `path` down the left edge of the cell.
+pub fn gutter(ui: &Ui, path: &[String]) {
+    let Some(mark) = mark(ui.ctx(), path) else {
+        return;
+    };
+    let cell = ui.max_rect();
+    let mut rect = cell.with_max_x(cell.left() + GUTTER_WIDTH);
+    if let Some(colour) = mark.change.and_then(colour) {
+        ui.painter().rect_filled(rect, 0.0, colour);
+    }
+    if mark.removed > 0
+        && let Some(colour) = colour(Change::Removed)
+    {
+        let tick = rect
+            .with_min_y(cell.bottom() - GUTTER_WIDTH)
+            .with_max_x(cell.left() + 8.0);
+        ui.painter().rect_filled(tick, 0.0, colour);
+        rect = rect.union(tick);
+    }
+    ui.interact(
+        rect.expand2(vec2(2.0, 0.0)),
+        ui.id().with("change_mark"),
+        Sense::hover(),
+    )
+    .on_hover_text(mark.describe());
+}
+
+/// What the review panel asks of the rest of the app after a frame.
+#[derive(Default)]
+pub struct ChangesResponse {
+    pub command: Option<Command>,
+    pub reveal: Option<Vec<String>>,
+    pub save: bool,
+}
+
+/// Keeps the change marks up to date, and lists the changes for review.
+#[derive(Default)]
+pub struct Changes {
+    open: bool,
+    /// The settled revision of the document the marks were worked out from.
+    settled: Option<u64>,
+    /// The rows listed for review, as indices into the snapshot's rows.
+    listed: Vec<usize>,
+}
+
+impl Changes {
+    pub const fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// Works out the changes again if the document settled on a change since last time. Diffing
+    /// the whole document is too slow to do on every keystroke or step of a drag.
+    pub fn refresh(&mut self, ctx: &Context, document: &Document) {
+        if self.settled == Some(document.settled()) {
+            return;
+        }
+        self.settled = Some(document.settled());
+        let baseline = document.baseline().clone();
+        let rows = diff::changes(&baseline, document.root());
+        let roots = [baseline.as_ref(), document.root()];
+        self.listed = (0..rows.len())
+            .filter(|&i| !rows[i].inherited && !rows[i].is_ancestor(roots))
+            .collect();
+        let marks = marks(&rows);
+        let snapshot = Arc::new(Snapshot {
+            baseline,
+            rows,
+            marks,
+        });
+        ctx.data_mut(|d| d.insert_temp(snapshot_id(), snapshot));
+    }
+
+    pub fn show(&mut self, ctx: &Context, document: &Document) -> ChangesResponse {
+        let mut ret = ChangesResponse::default();
+        if !self.open {
+            return ret;
+        }
+        let Some(snapshot) = snapshot(ctx) else {
+            return ret;
+        };
+        let mut open = true;
+        egui::Window::new("Review Changes")
+            .id(Id::new("ReviewChanges"))
+            .open(&mut open)
+            .default_size([640.0, 400.0])
+            .show(ctx, |ui| ret = self.ui(ui, &snapshot, document));
+        self.open = open;
+        ret
+    }
+
+    fn ui(&self, ui: &mut Ui, snapshot: &Snapshot, document: &Document) -> ChangesResponse {
+        let mut ret = ChangesResponse::default();
+        ui.horizontal(|ui| {
+            let count = self.listed.len();
+            let s = if count == 1 { "" } else { "s" };
+            ui.label(format!("{count} change{s} since the file was saved"));
+            if ui
+                .add_enabled(document.is_unsaved(), egui::Button::new("Save"))
+                .clicked()
+            {
+                ret.save = true;
+            }
+        });
+        ui.separator();
+
+        let roots = [snapshot.baseline.as_ref(), document.root()];
+        TableBuilder::new(ui)
+            .id_salt("review_changes")
+            .striped(true)
+            .resizable(true)
+            .cell_layout(Layout::left_to_right(Align::Center))
+            .column(Column::auto())
+            .column(Column::initial(200.0).at_least(60.0).clip(true))
+            .column(Column::remainder().clip(true))
+            .column(Column::auto())
+            .auto_shrink([false, false])
+            .header(20.0, |mut header| {
+                for title in ["Change", "Key Path", "Value", ""] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(ROW_HEIGHT, self.listed.len(), |mut row| {
+                    let diff = &snapshot.rows[self.listed[row.index()]];
+                    row.col(|ui| {
+                        let mut text = RichText::new(diff.change.label());
+                        if let Some(colour) = colour(diff.change) {
+                            text = text.color(colour);
+                        }
+                        ui.label(text);
+                    });
+                    row.col(|ui| {
+                        let side = if diff.present[1] {
+                            Side::Right
+                        } else {
+                            Side::Left
+                        };
+                        let path = &diff.paths[side.index()];
+                        let title = if path.is_empty() {
+                            "Root".to_owned()
+                        } else {
+                            path.join(" › ")
+                        };
+                        if diff.present[1] {
+                            if ui.link(title).clicked() {
+                                ret.reveal = Some(path.clone());
+                            }
+                        } else {
+                            ui.add(Label::new(title).truncate());
+                        }
+                    });
+                    row.col(|ui| {
+                        let values = [0, 1].map(|i| {
+                            diff.present[i]
+                                .then(|| try_pv(&diff.paths[i], roots[i]))
+                                .flatten()
+                                .map(summary)
+                        });
+                        let text = match values {
+                            [Some(old), Some(new)] if old != new => format!("{old} → {new}"),
+                            [Some(v), _] | [None, Some(v)] => v,
+                            [None, None] => String::new(),
+                        };
+                        ui.add(Label::new(text).truncate());
+                    });
+                    row.col(|ui| {
+                        if ui
+                            .small_button("Revert")
+                            .on_hover_text("Put this back the way it was saved")
+                            .clicked()
+                        {
+                            ret.command = diff::copy(diff, Side::Right, roots);
+                        }
+                    });
+                });
+            });
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::Context;
+    use plist::{Dictionary, Value};
+
+    use super::{Changes, mark};
+    use crate::{
+        diff::Change,
+        document::{Command, Document},
+    };
+
+    #[test]
+    fn refreshing() {
+        let ctx = Context::default();
+        let root = Value::Dictionary(Dictionary::from_iter([("a", Value::from(1))]));
+        let mut document = Document::new(root);
+        let mut changes = Changes::default();
+        let a = vec!["a".to_owned()];
+        changes.refresh(&ctx, &document);
+        assert_eq!(mark(&ctx, &a), None);
+
+        document.apply(Command::Set {
+            path: a.clone(),
+            value: Value::from(2),
+        });
+        changes.refresh(&ctx, &document);
+        assert_eq!(mark(&ctx, &a), None, "the edit hasn't settled");
+        document.commit();
+        changes.refresh(&ctx, &document);
+        assert_eq!(mark(&ctx, &a).and_then(|v| v.change), Some(Change::Changed));
+
+        document.undo();
+        changes.refresh(&ctx, &document);
+        assert_eq!(mark(&ctx, &a), None);
+        document.redo();
+        document.mark_saved();
+        changes.refresh(&ctx, &document);
+        assert_eq!(mark(&ctx, &a), None);
+    }
+}
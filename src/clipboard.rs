@@ -0,0 +1,325 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use egui::{Context, Id, Key, KeyboardShortcut, Modifiers, ViewportCommand};
+use plist::{Dictionary, Value, XmlWriteOptions};
+
+use crate::{
+    document::Command,
+    utils::{cmp_paths, try_pv, unique_key},
+};
+
+pub const COPY_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::C);
+pub const CUT_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::X);
+pub const PASTE_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::V);
+pub const PASTE_CHILD_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::V);
+
+#[derive(Clone, Debug)]
+struct PendingPaste {
+    /// The Data values leading to the document pasted into, if it's a nested one.
+    nesting: Vec<Vec<String>>,
+    path: Vec<String>,
+    as_child: bool,
+}
+
+fn pending_id() -> Id {
+    Id::new("PendingPaste")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Serialises nodes as the entries of an XML plist dictionary, without the dictionary around
+/// them, so their keys survive the round trip.
+#[must_use]
+pub fn to_text<'a>(items: impl IntoIterator<Item = (&'a str, &'a Value)>) -> Option<String> {
+    let mut dict = Dictionary::new();
+    for (k, v) in items {
+        dict.insert(unique_key(&dict, k), v.clone());
+    }
+    let options = XmlWriteOptions::default().root_element(false);
+    let mut ret = String::new();
+    for (k, v) in dict {
+        let mut buf = Vec::new();
+        v.to_writer_xml_with_options(&mut buf, &options).ok()?;
+        ret.push_str(&format!("<key>{}</key>\n", escape(&k)));
+        ret.push_str(std::str::from_utf8(&buf).ok()?);
+        ret.push('\n');
+    }
+    Some(ret)
+}
+
+/// Parses dictionary entries without the dictionary around them, as [`to_text`] writes them.
+fn parse_entries(text: &str) -> Option<Dictionary> {
+    let text = format!(r#"<plist version="1.0"><dict>{text}</dict></plist>"#);
+    Value::from_reader_xml(text.as_bytes())
+        .ok()?
+        .into_dictionary()
+}
+
+fn parse(text: &str) -> Option<Value> {
+    Value::from_reader_xml(text.as_bytes())
+        .ok()
+        .or_else(|| {
+            Value::from_reader_xml(format!(r#"<plist version="1.0">{text}</plist>"#).as_bytes())
+                .ok()
+        })
+        .or_else(|| serde_json::from_str(text).ok())
+        .or_else(|| Value::from_reader_ascii(text.as_bytes()).ok())
+}
+
+/// Parses clipboard text as XML plist entries, an XML plist, JSON or OpenStep, falling back to a
+/// plain string.
+///
+/// Entries, as nodes are copied, keep their keys. Anything else is a single value, even if it's
+/// a dictionary.
+#[must_use]
+pub fn from_text(text: &str) -> Vec<(Option<String>, Value)> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Vec::new();
+    }
+    if let Some(entries) = parse_entries(text) {
+        return entries.into_iter().map(|(k, v)| (Some(k), v)).collect();
+    }
+    vec![(
+        None,
+        parse(text).unwrap_or_else(|| Value::String(text.to_owned())),
+    )]
+}
+
+/// Copies the nodes at `paths` to the clipboard. If `cut` is set, returns the edit that removes
+/// them, which the root is left out of.
+pub fn copy(ctx: &Context, paths: &[Vec<String>], p: &Value, cut: bool) -> Option<Command> {
+    let text = to_text(paths.iter().filter_map(|v| {
+        let key = v.last().map_or("Root", String::as_str);
+        Some((key, try_pv(v, p)?))
+    }))?;
+    ctx.copy_text(text);
+    if !cut {
+        return None;
+    }
+    let mut paths = paths
+        .iter()
+        .filter(|v| !v.is_empty())
+        .cloned()
+        .collect::<Vec<_>>();
+    // Later siblings first, so removing one doesn't move the others.
+    paths.sort_by(|a, b| cmp_paths(b, a));
+    Command::batch(
+        paths
+            .into_iter()
+            .map(|path| Command::Remove { path })
+            .collect(),
+    )
+}
+
+/// Inserts pasted entries after the node at `path`, or at the end of it when `as_child` is set.
+///
+/// The root can only be pasted into.
+#[must_use]
+pub fn paste(
+    path: &[String],
+    as_child: bool,
+    items: Vec<(Option<String>, Value)>,
+    p: &Value,
+) -> Option<Command> {
+    let (container, after) = match path.split_last() {
+        Some((k, parent)) if !as_child => (parent, Some(k)),
+        _ => (path, None),
+    };
+    let child = |k: String| {
+        container
+            .iter()
+            .cloned()
+            .chain(std::iter::once(k))
+            .collect()
+    };
+    let commands = match try_pv(container, p)? {
+        Value::Dictionary(v) => {
+            let items = items
+                .into_iter()
+                .map(|(k, value)| (k.unwrap_or_else(|| "New Child".to_owned()), value));
+            let Some(after) = after.filter(|k| v.contains_key(k.as_str())) else {
+                return Command::batch(
+                    items
+                        .map(|(k, value)| Command::Insert {
+                            path: child(k),
+                            value,
+                        })
+                        .collect(),
+                );
+            };
+            // Inserting appends, so the dictionary is set with the entries after the node.
+            let mut pasted = v.clone();
+            let keys = items
+                .map(|(k, value)| {
+                    let key = unique_key(&pasted, &k);
+                    pasted.insert(key.clone(), value);
+                    key
+                })
+                .collect::<Vec<_>>();
+            let mut dict = Dictionary::new();
+            for k in v.keys() {
+                dict.insert(k.clone(), pasted.remove(k)?);
+                if k == after {
+                    for k in &keys {
+                        dict.insert(k.clone(), pasted.remove(k)?);
+                    }
+                }
+            }
+            vec![Command::Set {
+                path: container.to_vec(),
+                value: Value::Dictionary(dict),
+            }]
+        }
+        Value::Array(arr) => {
+            let index = after
+                .and_then(|v| v.parse::<usize>().ok())
+                .map_or(arr.len(), |v| (v + 1).min(arr.len()));
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(i, (_, value))| Command::Insert {
+                    path: child((index + i).to_string()),
+                    value,
+                })
+                .collect()
+        }
+        _ => return None,
+    };
+    Command::batch(commands)
+}
+
+/// Asks the platform for the clipboard contents, which arrive as a paste event on the next frame.
+pub fn request_paste(ctx: &Context, nesting: &[Vec<String>], path: &[String], as_child: bool) {
+    ctx.data_mut(|d| {
+        d.insert_temp(
+            pending_id(),
+            PendingPaste {
+                nesting: nesting.to_vec(),
+                path: path.to_vec(),
+                as_child,
+            },
+        );
+    });
+    ctx.send_viewport_cmd(ViewportCommand::RequestPaste);
+}
+
+#[must_use]
+pub fn take_pending_paste(ctx: &Context) -> Option<(Vec<Vec<String>>, Vec<String>, bool)> {
+    ctx.data_mut(|d| {
+        let ret = d.get_temp::<PendingPaste>(pending_id());
+        d.remove::<PendingPaste>(pending_id());
+        ret
+    })
+    .map(|v| (v.nesting, v.path, v.as_child))
+}
+
+#[cfg(test)]
+mod tests {
+    use plist::{Dictionary, Value};
+
+    use super::{from_text, paste, to_text};
+
+    fn dict<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Dictionary(Dictionary::from_iter(entries))
+    }
+
+    fn path(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|&v| v.to_owned()).collect()
+    }
+
+    fn keys(value: &Value) -> Vec<String> {
+        value.as_dictionary().unwrap().keys().cloned().collect()
+    }
+
+    fn pasted(root: &Value, at: &[&str], as_child: bool, text: &str) -> Value {
+        let mut ret = root.clone();
+        paste(&path(at), as_child, from_text(text), root)
+            .unwrap()
+            .apply(&mut ret)
+            .unwrap();
+        ret
+    }
+
+    #[test]
+    fn round_trip() {
+        let inner = dict([("x", Value::from(1))]);
+        let text = to_text([
+            ("a <&> b", &Value::from(1)),
+            ("d", &inner),
+            ("d", &Value::Boolean(true)),
+        ])
+        .unwrap();
+        assert_eq!(
+            from_text(&text),
+            [
+                (Some("a <&> b".to_owned()), Value::from(1)),
+                (Some("d".to_owned()), inner),
+                (Some("d Duplicate".to_owned()), Value::Boolean(true)),
+            ]
+        );
+    }
+
+    #[test]
+    fn values() {
+        let inner = dict([("x", Value::from(1))]);
+        let mut xml = Vec::new();
+        inner.to_writer_xml(&mut xml).unwrap();
+        for text in [
+            std::str::from_utf8(&xml).unwrap(),
+            "<dict><key>x</key><integer>1</integer></dict>",
+            r#"{"x": 1}"#,
+            "{x = 1;}",
+        ] {
+            assert_eq!(from_text(text), [(None, inner.clone())], "{text}");
+        }
+        assert_eq!(from_text("<string>a</string>"), [(None, Value::from("a"))]);
+        assert_eq!(from_text(" just text "), [(None, Value::from("just text"))]);
+        assert_eq!(from_text("  "), []);
+    }
+
+    #[test]
+    fn paste_into_dictionary() {
+        let root = dict([
+            ("a", Value::from(0)),
+            ("b", Value::from(1)),
+            ("c", Value::from(2)),
+        ]);
+        let text = to_text([("a", &Value::from(3)), ("n", &Value::from(4))]).unwrap();
+
+        let value = pasted(&root, &["a"], false, &text);
+        assert_eq!(keys(&value), path(&["a", "a Duplicate", "n", "b", "c"]));
+        let value = pasted(&root, &["c"], false, &text);
+        assert_eq!(keys(&value), path(&["a", "b", "c", "a Duplicate", "n"]));
+        let value = pasted(&root, &[], true, &text);
+        assert_eq!(keys(&value), path(&["a", "b", "c", "a Duplicate", "n"]));
+
+        // A dictionary pasted as a value goes in as one.
+        let value = pasted(&root, &["a"], false, "<dict><key>b</key><true/></dict>");
+        assert_eq!(keys(&value), path(&["a", "New Child", "b", "c"]));
+        assert_eq!(
+            value.as_dictionary().unwrap()["New Child"],
+            dict([("b", Value::Boolean(true))])
+        );
+    }
+
+    #[test]
+    fn paste_into_array() {
+        let root = Value::Array(vec![Value::from(0), Value::from(1)]);
+        let text = to_text([("a", &Value::from(2)), ("b", &Value::from(3))]).unwrap();
+        let items = |v: &[i64]| Value::Array(v.iter().copied().map(Value::from).collect());
+        assert_eq!(pasted(&root, &["0"], false, &text), items(&[0, 2, 3, 1]));
+        assert_eq!(pasted(&root, &["1"], false, &text), items(&[0, 1, 2, 3]));
+        assert_eq!(pasted(&root, &[], true, &text), items(&[0, 1, 2, 3]));
+        assert_eq!(
+            paste(&path(&["0", "x"]), false, from_text(&text), &root),
+            None
+        );
+    }
+}
@@ -0,0 +1,302 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::path::PathBuf;
+
+use egui::{Align, Color32, Context, Id, Label, Layout, RichText, Ui};
+use egui_extras::{Column, TableBuilder};
+use plist::Value;
+
+use crate::{
+    diff::{self, Change, Row, Side},
+    document::Document,
+    find::value_text,
+    format::Format,
+    patch,
+    utils::try_pv,
+};
+
+const ROW_HEIGHT: f32 = 20.0;
+const INDENT: f32 = 14.0;
+
+/// The colour nodes with `change` are marked with, if any.
+#[must_use]
+pub const fn colour(change: Change) -> Option<Color32> {
+    let (r, g, b) = match change {
+        Change::Same => return None,
+        Change::Added => (60, 180, 75),
+        Change::Removed => (220, 60, 60),
+        Change::Changed => (230, 180, 40),
+        Change::TypeChanged => (200, 100, 220),
+        Change::Moved => (70, 130, 230),
+    };
+    Some(Color32::from_rgb(r, g, b))
+}
+
+/// A one-line rendering of a value, with containers given as their size.
+#[must_use]
+pub fn summary(v: &Value) -> String {
+    let count = |len: usize, what: &str| {
+        let s = if len == 1 { "" } else { "s" };
+        format!("{len} {what}{s}")
+    };
+    match v {
+        Value::Dictionary(v) => count(v.len(), "key"),
+        Value::Array(v) => count(v.len(), "item"),
+        Value::Boolean(v) => v.to_string(),
+        Value::Date(v) => v.to_xml_format(),
+        v => value_text(v).unwrap_or_default(),
+    }
+}
+
+/// One of the files being compared.
+#[derive(Default)]
+struct Pane {
+    /// The file, and the encoding it's written back in.
+    path: Option<(PathBuf, Format)>,
+    document: Document,
+}
+
+impl Pane {
+    fn open(&mut self) -> Result<(), String> {
+        let Some(path) = rfd::FileDialog::new().pick_file() else {
+            return Ok(());
+        };
+        let (root, format) = Format::read_file(&path)?;
+        self.document.replace(root);
+        self.path = Some((path, format));
+        Ok(())
+    }
+
+    fn save(&mut self) -> Result<(), String> {
+        let Some((path, format)) = &self.path else {
+            return Ok(());
+        };
+        format.write_file(path, self.document.root())?;
+        self.document.mark_saved();
+        Ok(())
+    }
+
+    fn ui(&mut self, ui: &mut Ui) -> Result<(), String> {
+        let mut ret = Ok(());
+        ui.horizontal(|ui| {
+            if ui.button("Open…").clicked() {
+                ret = self.open();
+            }
+            if ui
+                .add_enabled(
+                    self.path.is_some() && self.document.is_unsaved(),
+                    egui::Button::new("Save"),
+                )
+                .clicked()
+            {
+                ret = self.save();
+            }
+            if ui
+                .add_enabled(self.document.can_undo(), egui::Button::new("Undo"))
+                .clicked()
+            {
+                self.document.undo();
+            }
+            if ui
+                .add_enabled(self.document.can_redo(), egui::Button::new("Redo"))
+                .clicked()
+            {
+                self.document.redo();
+            }
+            let name = self
+                .path
+                .as_ref()
+                .map_or_else(|| "No file".into(), |(v, _)| v.display().to_string());
+            let unsaved = if self.document.is_unsaved() { " *" } else { "" };
+            ui.add(Label::new(format!("{name}{unsaved}")).truncate());
+        });
+        ret
+    }
+}
+
+/// Two files side by side, lined up node by node, with changes copied across a node at a time.
+#[derive(Default)]
+pub struct Compare {
+    open: bool,
+    panes: [Pane; 2],
+    rows: Vec<Row>,
+    /// The rows shown, as indices into `rows`.
+    shown: Vec<usize>,
+    /// The revisions of the panes `rows` was worked out from.
+    revisions: Option<[u64; 2]>,
+    show_unchanged: bool,
+    error: Option<String>,
+}
+
+impl Compare {
+    pub const fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// Whether either file has changes copied into it that haven't been saved.
+    #[must_use]
+    pub fn is_unsaved(&self) -> bool {
+        self.panes.iter().any(|v| v.document.is_unsaved())
+    }
+
+    fn refresh(&mut self) {
+        let revisions = self.panes.each_ref().map(|v| v.document.revision());
+        if self.revisions == Some(revisions) {
+            return;
+        }
+        self.revisions = Some(revisions);
+        self.rows = diff::diff(self.panes[0].document.root(), self.panes[1].document.root());
+        self.filter();
+    }
+
+    /// Picks the rows to show: every row, or only changes and the rows leading to them.
+    fn filter(&mut self) {
+        if self.show_unchanged {
+            self.shown = (0..self.rows.len()).collect();
+            return;
+        }
+        self.shown.clear();
+        // Walking backwards, the next row shallower than this is an ancestor of a change.
+        let mut needed = 0;
+        for (i, row) in self.rows.iter().enumerate().rev() {
+            if row.change != Change::Same {
+                needed = needed.max(row.depth);
+            } else if row.depth < needed {
+                needed = row.depth;
+            } else {
+                continue;
+            }
+            self.shown.push(i);
+        }
+        self.shown.reverse();
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        if !self.open {
+            return;
+        }
+        let mut open = true;
+        egui::Window::new("Compare")
+            .id(Id::new("Compare"))
+            .open(&mut open)
+            .default_size([960.0, 540.0])
+            .show(ctx, |ui| self.ui(ui));
+        self.open = open;
+    }
+
+    fn ui(&mut self, ui: &mut Ui) {
+        ui.columns(2, |columns| {
+            for (pane, ui) in self.panes.iter_mut().zip(columns) {
+                if let Err(e) = pane.ui(ui) {
+                    self.error = Some(e);
+                }
+            }
+        });
+        self.refresh();
+
+        ui.horizontal(|ui| {
+            if ui
+                .checkbox(&mut self.show_unchanged, "Show unchanged")
+                .changed()
+            {
+                self.filter();
+            }
+            let changes = self.rows.iter().filter(|v| v.copyable()).count();
+            let s = if changes == 1 { "" } else { "s" };
+            ui.label(format!("{changes} change{s}"));
+            if ui
+                .add_enabled(changes > 0, egui::Button::new("Export Patch…"))
+                .on_hover_text("Save the steps that turn the left file into the right one")
+                .clicked()
+            {
+                let [left, right] = self.panes.each_ref().map(|v| v.document.root());
+                patch::save(ui.ctx(), left, right);
+            }
+            if let Some(error) = &self.error {
+                ui.label(RichText::new(error).color(ui.visuals().error_fg_color));
+            }
+        });
+        ui.separator();
+
+        let roots = self.panes.each_ref().map(|v| v.document.root());
+        let mut copy = None;
+        TableBuilder::new(ui)
+            .id_salt("compare")
+            .striped(true)
+            .resizable(true)
+            .cell_layout(Layout::left_to_right(Align::Center))
+            .column(Column::initial(200.0).at_least(60.0).clip(true))
+            .column(Column::remainder().clip(true))
+            .column(Column::auto())
+            .column(Column::initial(200.0).at_least(60.0).clip(true))
+            .column(Column::remainder().clip(true))
+            .auto_shrink([false, false])
+            .header(20.0, |mut header| {
+                for title in ["Key", "Value", "", "Key", "Value"] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(ROW_HEIGHT, self.shown.len(), |mut row| {
+                    let i = self.shown[row.index()];
+                    let diff = &self.rows[i];
+                    let mark = |ui: &mut Ui| {
+                        if let Some(colour) = colour(diff.change) {
+                            let colour = colour.gamma_multiply(0.2);
+                            ui.painter().rect_filled(ui.max_rect(), 0.0, colour);
+                        }
+                    };
+                    for side in [Side::Left, Side::Right] {
+                        if side == Side::Right {
+                            row.col(|ui| {
+                                mark(ui);
+                                if !diff.copyable() {
+                                    return;
+                                }
+                                for (label, to, hint) in [
+                                    ("→", Side::Right, "Copy to the right"),
+                                    ("←", Side::Left, "Copy to the left"),
+                                ] {
+                                    if ui.small_button(label).on_hover_text(hint).clicked() {
+                                        copy = Some((i, to));
+                                    }
+                                }
+                            });
+                        }
+                        let value = diff.present[side.index()]
+                            .then(|| try_pv(&diff.paths[side.index()], roots[side.index()]))
+                            .flatten();
+                        row.col(|ui| {
+                            mark(ui);
+                            let Some(_) = value else {
+                                return;
+                            };
+                            ui.add_space(diff.depth as f32 * INDENT);
+                            let key = diff.paths[side.index()]
+                                .last()
+                                .map_or("Root", String::as_str);
+                            ui.add(Label::new(key).truncate())
+                                .on_hover_text(diff.change.label());
+                        });
+                        row.col(|ui| {
+                            mark(ui);
+                            if let Some(value) = value {
+                                ui.add(Label::new(summary(value)).truncate());
+                            }
+                        });
+                    }
+                });
+            });
+
+        if let Some((i, to)) = copy
+            && let Some(command) = diff::copy(&self.rows[i], to, roots)
+        {
+            let document = &mut self.panes[to.index()].document;
+            document.apply(command);
+            document.commit();
+        }
+    }
+}
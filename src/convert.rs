@@ -0,0 +1,424 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use egui::{Context, Id};
+use plist::{Dictionary, Value};
+
+use crate::{
+    document::Command,
+    utils::{ValueType, format_real, int_from_i128, int_to_i128},
+};
+
+/// One way of turning a value into another type. `loss` says what gets thrown away, if anything.
+#[derive(Clone, Debug)]
+pub struct Conversion {
+    pub label: &'static str,
+    pub value: Value,
+    pub loss: Option<&'static str>,
+}
+
+impl Conversion {
+    const fn exact(label: &'static str, value: Value) -> Self {
+        Self {
+            label,
+            value,
+            loss: None,
+        }
+    }
+
+    fn lossy(to: ValueType, loss: &'static str) -> Self {
+        Self {
+            label: "Replace",
+            value: to.default_value(),
+            loss: Some(loss),
+        }
+    }
+}
+
+/// Conversions waiting on the user, either because one is lossy or because there is a choice.
+#[derive(Clone, Debug, Default)]
+pub struct PendingConversion {
+    pub to: Option<ValueType>,
+    pub targets: Vec<(Vec<String>, Vec<Conversion>)>,
+}
+
+impl PendingConversion {
+    #[must_use]
+    pub fn needs_confirmation(&self) -> bool {
+        self.targets
+            .iter()
+            .any(|(_, v)| v.len() > 1 || v.iter().any(|v| v.loss.is_some()))
+    }
+
+    /// Sets the chosen option, or the first one for each target when there are several.
+    #[must_use]
+    pub fn apply(self, choice: usize) -> Option<Command> {
+        Command::batch(
+            self.targets
+                .into_iter()
+                .map(|(path, mut options)| {
+                    let i = if options.len() > choice { choice } else { 0 };
+                    let value = options.swap_remove(i).value;
+                    Command::Set { path, value }
+                })
+                .collect(),
+        )
+    }
+}
+
+/// A conversion waiting on the user, along with the Data values leading to the document it
+/// applies to, if it's a nested one.
+#[derive(Clone, Debug)]
+struct Request {
+    nesting: Vec<Vec<String>>,
+    pending: PendingConversion,
+}
+
+fn pending_id() -> Id {
+    Id::new("PendingConversion")
+}
+
+pub fn request(ctx: &Context, nesting: &[Vec<String>], pending: PendingConversion) {
+    let nesting = nesting.to_vec();
+    ctx.data_mut(|d| d.insert_temp(pending_id(), Request { nesting, pending }));
+}
+
+#[must_use]
+pub fn pending(ctx: &Context) -> Option<(Vec<Vec<String>>, PendingConversion)> {
+    ctx.data_mut(|d| d.get_temp::<Request>(pending_id()))
+        .map(|v| (v.nesting, v.pending))
+}
+
+pub fn cancel(ctx: &Context) {
+    ctx.data_mut(|d| d.remove::<Request>(pending_id()));
+}
+
+fn int_value(v: i128) -> Option<Value> {
+    int_from_i128(v).map(Value::Integer)
+}
+
+fn from_string(s: &str, to: ValueType) -> Vec<Conversion> {
+    match to {
+        ValueType::Data => {
+            let mut ret = vec![Conversion::exact(
+                "UTF-8 bytes",
+                Value::Data(s.as_bytes().to_vec()),
+            )];
+            if !s.is_empty()
+                && let Ok(v) = hex::decode(s.trim())
+            {
+                ret.push(Conversion::exact("Hex bytes", Value::Data(v)));
+            }
+            ret
+        }
+        _ => to.parse_value(s).map_or_else(
+            || {
+                let loss = match to {
+                    ValueType::Integer => "The text is not an integer.",
+                    ValueType::Real => "The text is not a number.",
+                    ValueType::Boolean => "The text is not a boolean.",
+                    ValueType::Date => "The text is not an ISO 8601 date.",
+                    _ => unreachable!(),
+                };
+                vec![Conversion::lossy(to, loss)]
+            },
+            |v| vec![Conversion::exact("Parse", v)],
+        ),
+    }
+}
+
+fn from_scalar(value: &Value, to: ValueType) -> Vec<Conversion> {
+    let from = ValueType::from_val(&[], value);
+    let exact = |label, v| vec![Conversion::exact(label, v)];
+    match (value, to) {
+        (Value::String(s), _) => from_string(s, to),
+        (_, ValueType::String) => match value {
+            Value::Boolean(v) => exact("Text", Value::String(v.to_string())),
+            Value::Integer(v) => exact("Text", Value::String(v.to_string())),
+            Value::Real(v) => exact("Text", Value::String(format_real(*v))),
+            Value::Date(v) => exact("ISO 8601", Value::String(v.to_xml_format())),
+            Value::Data(v) => {
+                let mut ret = Vec::new();
+                if let Ok(s) = std::str::from_utf8(v) {
+                    ret.push(Conversion::exact("UTF-8 text", Value::String(s.into())));
+                }
+                ret.push(Conversion::exact(
+                    "Hex",
+                    Value::String(hex::encode_upper(v)),
+                ));
+                ret
+            }
+            _ => vec![Conversion::lossy(to, "The value cannot be shown as text.")],
+        },
+        (Value::Integer(v), ValueType::Real) => {
+            let v = int_to_i128(*v);
+            let real = v as f64;
+            if real as i128 == v {
+                exact("Number", Value::Real(real))
+            } else {
+                vec![Conversion {
+                    label: "Round",
+                    value: Value::Real(real),
+                    loss: Some("The integer is too large to be stored exactly as a real."),
+                }]
+            }
+        }
+        (Value::Integer(v), ValueType::Boolean) => match int_to_i128(*v) {
+            0 => exact("Number", Value::Boolean(false)),
+            1 => exact("Number", Value::Boolean(true)),
+            _ => vec![Conversion {
+                label: "Non-zero is true",
+                value: Value::Boolean(true),
+                loss: Some("Only 0 and 1 convert to a boolean exactly."),
+            }],
+        },
+        (Value::Real(v), ValueType::Integer) => {
+            let truncated = int_value(v.trunc() as i128).filter(|_| v.is_finite());
+            match truncated {
+                Some(i) if v.fract() == 0.0 => exact("Number", i),
+                Some(i) => vec![Conversion {
+                    label: "Truncate",
+                    value: i,
+                    loss: Some("The fractional part is discarded."),
+                }],
+                None => vec![Conversion::lossy(to, "The real is out of integer range.")],
+            }
+        }
+        (Value::Real(v), ValueType::Boolean) if *v == 0.0 || *v == 1.0 => {
+            exact("Number", Value::Boolean(*v == 1.0))
+        }
+        (Value::Boolean(v), ValueType::Integer) => {
+            exact("Number", Value::Integer(u8::from(*v).into()))
+        }
+        (Value::Boolean(v), ValueType::Real) => {
+            exact("Number", Value::Real(f64::from(u8::from(*v))))
+        }
+        _ => vec![Conversion::lossy(
+            to,
+            match from {
+                ValueType::Data => "The data is discarded.",
+                ValueType::Date => "The date is discarded.",
+                _ => "The value is discarded.",
+            },
+        )],
+    }
+}
+
+/// Every sensible way of converting `value` to `to`, best first.
+#[must_use]
+pub fn conversions(value: &Value, key: &str, to: ValueType) -> Vec<Conversion> {
+    let from = ValueType::from_val(&[], value);
+    if from == to {
+        return vec![Conversion::exact("Keep", value.clone())];
+    }
+    match (value, to) {
+        (Value::Array(v), ValueType::Dictionary) => vec![Conversion::exact(
+            "Index keys",
+            Value::Dictionary(
+                v.iter()
+                    .enumerate()
+                    .map(|(i, v)| (i.to_string(), v.clone()))
+                    .collect(),
+            ),
+        )],
+        (Value::Dictionary(v), ValueType::Array) => vec![Conversion {
+            label: "Keep values",
+            value: Value::Array(v.values().cloned().collect()),
+            loss: (!v.is_empty()).then_some("The keys are discarded."),
+        }],
+        (Value::Array(v), _)
+            if v.len() == 1 && !ValueType::from_val(&[], &v[0]).is_expandable() =>
+        {
+            let ret = conversions(&v[0], key, to);
+            if ret.iter().all(|v| v.loss.is_some()) {
+                vec![Conversion::lossy(to, "The array is discarded.")]
+            } else {
+                ret
+            }
+        }
+        (Value::Array(v), _) if v.is_empty() => {
+            vec![Conversion::exact("Empty", to.default_value())]
+        }
+        (Value::Dictionary(v), _) if v.is_empty() => {
+            vec![Conversion::exact("Empty", to.default_value())]
+        }
+        (Value::Array(_) | Value::Dictionary(_), _) => {
+            vec![Conversion::lossy(to, "The children are discarded.")]
+        }
+        (_, ValueType::Array) => vec![Conversion::exact("Wrap", Value::Array(vec![value.clone()]))],
+        (_, ValueType::Dictionary) => vec![Conversion::exact(
+            "Wrap",
+            Value::Dictionary(Dictionary::from_iter([(key, value.clone())])),
+        )],
+        _ => from_scalar(value, to),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plist::{Dictionary, Value};
+
+    use super::{PendingConversion, conversions};
+    use crate::{document::Command, utils::ValueType};
+
+    /// The label, value and whether it's lossy of each way of converting `value` to `to`.
+    fn options(value: impl Into<Value>, to: ValueType) -> Vec<(&'static str, Value, bool)> {
+        conversions(&value.into(), "k", to)
+            .into_iter()
+            .map(|v| (v.label, v.value, v.loss.is_some()))
+            .collect()
+    }
+
+    fn needs_confirmation(value: impl Into<Value>, to: ValueType) -> bool {
+        PendingConversion {
+            to: Some(to),
+            targets: vec![(Vec::new(), conversions(&value.into(), "k", to))],
+        }
+        .needs_confirmation()
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(
+            options("42", ValueType::Integer),
+            [("Parse", 42.into(), false)]
+        );
+        assert_eq!(
+            options(" 0x2A ", ValueType::Integer),
+            [("Parse", 42.into(), false)]
+        );
+        assert_eq!(
+            options("yes", ValueType::Boolean),
+            [("Parse", true.into(), false)]
+        );
+        assert_eq!(
+            options("forty-two", ValueType::Integer),
+            [("Replace", 0.into(), true)]
+        );
+        assert_eq!(
+            options(42, ValueType::String),
+            [("Text", "42".into(), false)]
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(
+            options(42, ValueType::Real),
+            [("Number", 42.0.into(), false)]
+        );
+        assert_eq!(
+            options(1_u64 << 53, ValueType::Real),
+            [("Number", 9_007_199_254_740_992.0.into(), false)]
+        );
+        let [(label, _, lossy)] = options((1_u64 << 53) + 1, ValueType::Real)[..] else {
+            panic!("expected one option");
+        };
+        assert_eq!((label, lossy), ("Round", true));
+        assert!(options(u64::MAX, ValueType::Real)[0].2);
+
+        assert_eq!(
+            options(true, ValueType::Integer),
+            [("Number", 1.into(), false)]
+        );
+        assert_eq!(
+            options(false, ValueType::Integer),
+            [("Number", 0.into(), false)]
+        );
+        assert_eq!(
+            options(2.5, ValueType::Integer),
+            [("Truncate", 2.into(), true)]
+        );
+        assert_eq!(
+            options(2, ValueType::Boolean),
+            [("Non-zero is true", true.into(), true)]
+        );
+    }
+
+    #[test]
+    fn data() {
+        assert_eq!(
+            options("AB", ValueType::Data),
+            [
+                ("UTF-8 bytes", Value::Data(b"AB".to_vec()), false),
+                ("Hex bytes", Value::Data(vec![0xab]), false),
+            ]
+        );
+        assert_eq!(
+            options("héllo", ValueType::Data),
+            [("UTF-8 bytes", Value::Data("héllo".into()), false)]
+        );
+        assert_eq!(
+            options(Value::Data(b"AB".to_vec()), ValueType::String),
+            [
+                ("UTF-8 text", "AB".into(), false),
+                ("Hex", "4142".into(), false),
+            ]
+        );
+        assert_eq!(
+            options(Value::Data(vec![0xff, 0x00]), ValueType::String),
+            [("Hex", "FF00".into(), false)]
+        );
+    }
+
+    #[test]
+    fn containers() {
+        assert_eq!(
+            options(42, ValueType::Array),
+            [("Wrap", Value::Array(vec![42.into()]), false)]
+        );
+        assert_eq!(
+            options(42, ValueType::Dictionary),
+            [(
+                "Wrap",
+                Value::Dictionary(Dictionary::from_iter([("k", Value::from(42))])),
+                false
+            )]
+        );
+        assert_eq!(
+            options(Value::Array(vec!["42".into()]), ValueType::Integer),
+            [("Parse", 42.into(), false)]
+        );
+        assert_eq!(
+            options(Value::Array(vec!["x".into()]), ValueType::Integer),
+            [("Replace", 0.into(), true)]
+        );
+        let dict = Value::Dictionary(Dictionary::from_iter([("a", Value::from(1))]));
+        assert_eq!(
+            options(dict, ValueType::Array),
+            [("Keep values", Value::Array(vec![1.into()]), true)]
+        );
+    }
+
+    #[test]
+    fn confirmation() {
+        assert!(!needs_confirmation("42", ValueType::Integer));
+        assert!(!needs_confirmation(true, ValueType::Integer));
+        assert!(!needs_confirmation(42, ValueType::Array));
+        assert!(needs_confirmation("forty-two", ValueType::Integer));
+        assert!(needs_confirmation((1_u64 << 53) + 1, ValueType::Real));
+        assert!(needs_confirmation(2.5, ValueType::Integer));
+        assert!(needs_confirmation(Value::Data(vec![1]), ValueType::Date));
+        assert!(needs_confirmation(
+            Value::Array(vec![1.into(), 2.into()]),
+            ValueType::String
+        ));
+        // A choice between two exact conversions is still the user's to make.
+        assert!(needs_confirmation("AB", ValueType::Data));
+
+        let pending = PendingConversion {
+            to: Some(ValueType::Data),
+            targets: vec![(
+                vec!["a".into()],
+                conversions(&"AB".into(), "a", ValueType::Data),
+            )],
+        };
+        assert_eq!(
+            pending.apply(1),
+            Some(Command::Batch(vec![Command::Set {
+                path: vec!["a".into()],
+                value: Value::Data(vec![0xab]),
+            }]))
+        );
+    }
+}
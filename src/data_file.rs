@@ -0,0 +1,121 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use egui::{Context, Id};
+use plist::Value;
+use sha2::{Digest, Sha256};
+
+use crate::document::Command;
+
+/// The outcome of the last load or save, shown until acknowledged.
+#[derive(Clone, Debug, Default)]
+struct Report {
+    heading: String,
+    message: String,
+}
+
+fn report_id() -> Id {
+    Id::new("DataFileReport")
+}
+
+fn report(ctx: &Context, heading: impl Into<String>, message: impl Into<String>) {
+    ctx.data_mut(|d| {
+        d.insert_temp(
+            report_id(),
+            Report {
+                heading: heading.into(),
+                message: message.into(),
+            },
+        );
+    });
+}
+
+#[must_use]
+pub fn summary(bytes: &[u8]) -> String {
+    format!(
+        "{} bytes\nSHA-256: {}",
+        bytes.len(),
+        hex::encode(Sha256::digest(bytes))
+    )
+}
+
+fn load_from(ctx: &Context, dialog: rfd::FileDialog, path: &[String]) -> Option<Command> {
+    let file = dialog.pick_file()?;
+    match std::fs::read(&file) {
+        Ok(bytes) => {
+            report(
+                ctx,
+                "Loaded data",
+                format!("{}\n{}", file.display(), summary(&bytes)),
+            );
+            Some(Command::Set {
+                path: path.to_vec(),
+                value: Value::Data(bytes),
+            })
+        }
+        Err(e) => {
+            report(ctx, "Error while loading data", e.to_string());
+            None
+        }
+    }
+}
+
+/// Replaces the data at `path` with the contents of a file picked by the user.
+#[must_use]
+pub fn load(ctx: &Context, path: &[String]) -> Option<Command> {
+    load_from(ctx, rfd::FileDialog::new().set_title("Load Data"), path)
+}
+
+/// Like [`load`], but only offers image files.
+#[must_use]
+pub fn load_image(ctx: &Context, path: &[String]) -> Option<Command> {
+    load_from(
+        ctx,
+        rfd::FileDialog::new()
+            .set_title("Replace Image")
+            .add_filter("Images", &["png", "jpg", "jpeg", "tif", "tiff", "icns"]),
+        path,
+    )
+}
+
+/// Writes `bytes` to a file picked by the user, suggesting `key` as the name.
+pub fn save(ctx: &Context, key: &str, bytes: &[u8]) {
+    let Some(file) = rfd::FileDialog::new()
+        .set_title("Save Data")
+        .set_file_name(format!("{key}.bin"))
+        .save_file()
+    else {
+        return;
+    };
+    match std::fs::write(&file, bytes) {
+        Ok(()) => report(
+            ctx,
+            "Saved data",
+            format!("{}\n{}", file.display(), summary(bytes)),
+        ),
+        Err(e) => report(ctx, "Error while saving data", e.to_string()),
+    }
+}
+
+pub fn show_report(ctx: &Context) {
+    let Some(report) = ctx.data_mut(|d| d.get_temp::<Report>(report_id())) else {
+        return;
+    };
+    let mut acked = false;
+    egui::Modal::new(Id::new("DataFileModal")).show(ctx, |ui| {
+        ui.heading(report.heading);
+        ui.separator();
+        ui.label(report.message);
+        ui.separator();
+        egui::Sides::new().show(
+            ui,
+            |_| {},
+            |ui| {
+                acked = ui.button("Okay").clicked();
+            },
+        )
+    });
+    if acked {
+        ctx.data_mut(|d| d.remove::<Report>(report_id()));
+    }
+}
@@ -0,0 +1,682 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::{collections::HashSet, mem::discriminant};
+
+use plist::{Dictionary, Value};
+
+use crate::{
+    document::Command,
+    utils::{identical, try_pv},
+};
+
+/// Past this many cells, arrays are aligned by index rather than by content.
+const MAX_LCS_CELLS: usize = 1 << 22;
+
+/// How a node differs between the two sides.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    Same,
+    /// Only on the right.
+    Added,
+    /// Only on the left.
+    Removed,
+    /// On both sides with the same type, but different contents.
+    Changed,
+    /// On both sides with different types.
+    TypeChanged,
+    /// Unchanged, but somewhere else: reordered within its dictionary, or removed on one side
+    /// and added on the other.
+    Moved,
+}
+
+impl Change {
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Same => "Unchanged",
+            Self::Added => "Added",
+            Self::Removed => "Removed",
+            Self::Changed => "Changed",
+            Self::TypeChanged => "Type changed",
+            Self::Moved => "Moved",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    #[must_use]
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    #[must_use]
+    pub const fn other(self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+}
+
+/// A node of either side, lined up with its counterpart on the other.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Row {
+    /// The path on each side. On a side that lacks the node, where it would go.
+    pub paths: [Vec<String>; 2],
+    pub present: [bool; 2],
+    pub depth: usize,
+    pub change: Change,
+    /// Whether the row is inside a node that was added, removed or retyped as a whole, so that
+    /// only that node can be copied across.
+    pub inherited: bool,
+}
+
+impl Row {
+    /// Whether the row can be copied from one side to the other.
+    #[must_use]
+    pub fn copyable(&self) -> bool {
+        !self.inherited && self.change != Change::Same
+    }
+
+    /// Whether the row is a container only there because something below it changed.
+    #[must_use]
+    pub fn is_ancestor(&self, roots: [&Value; 2]) -> bool {
+        self.change == Change::Changed
+            && matches!(
+                [0, 1].map(|i| try_pv(&self.paths[i], roots[i])),
+                [Some(Value::Dictionary(_)), Some(Value::Dictionary(_))]
+                    | [Some(Value::Array(_)), Some(Value::Array(_))]
+            )
+    }
+}
+
+fn child(path: &[String], k: impl Into<String>) -> Vec<String> {
+    path.iter()
+        .cloned()
+        .chain(std::iter::once(k.into()))
+        .collect()
+}
+
+/// The pairs of indices of a longest common subsequence of `a` and `b`.
+fn lcs<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    let (n, m) = (a.len(), b.len());
+    let mut table = vec![0u32; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i * (m + 1) + j] = if a[i] == b[j] {
+                table[(i + 1) * (m + 1) + j + 1] + 1
+            } else {
+                table[(i + 1) * (m + 1) + j].max(table[i * (m + 1) + j + 1])
+            };
+        }
+    }
+    let mut ret = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            ret.push((i, j));
+            i += 1;
+            j += 1;
+        } else if table[(i + 1) * (m + 1) + j] >= table[i * (m + 1) + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    ret
+}
+
+/// Lines up the items of two arrays: equal items by a longest common subsequence, then what's
+/// left between them by position. Items left over that equal one left over on the other side
+/// aren't paired, so they show up as moved.
+fn align(a: &[Value], b: &[Value]) -> Vec<(Option<usize>, Option<usize>)> {
+    let prefix = a.iter().zip(b).take_while(|(a, b)| a == b).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (end_a, end_b) = (a.len() - suffix, b.len() - suffix);
+    let small = (end_a - prefix) * (end_b - prefix) <= MAX_LCS_CELLS;
+    let matched = if small {
+        lcs(&a[prefix..end_a], &b[prefix..end_b])
+            .into_iter()
+            .map(|(i, j)| (i + prefix, j + prefix))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut moved_a = vec![false; a.len()];
+    let mut moved_b = vec![false; b.len()];
+    if small {
+        let mut left_b = (prefix..end_b)
+            .filter(|j| !matched.iter().any(|v| v.1 == *j))
+            .collect::<Vec<_>>();
+        for i in (prefix..end_a).filter(|i| !matched.iter().any(|v| v.0 == *i)) {
+            if let Some(pos) = left_b.iter().position(|&j| a[i] == b[j]) {
+                moved_a[i] = true;
+                moved_b[left_b.remove(pos)] = true;
+            }
+        }
+    }
+
+    let mut ret = (0..prefix).map(|i| (Some(i), Some(i))).collect::<Vec<_>>();
+    let (mut i, mut j) = (prefix, prefix);
+    for (next_a, next_b) in matched.into_iter().chain(std::iter::once((end_a, end_b))) {
+        let gap_a = (i..next_a).filter(|&i| !moved_a[i]).collect::<Vec<_>>();
+        let gap_b = (j..next_b).filter(|&j| !moved_b[j]).collect::<Vec<_>>();
+        let paired = gap_a.len().min(gap_b.len());
+        ret.extend(
+            gap_a[..paired]
+                .iter()
+                .zip(&gap_b[..paired])
+                .map(|(&i, &j)| (Some(i), Some(j))),
+        );
+        ret.extend(
+            (i..next_a)
+                .filter(|i| !gap_a[..paired].contains(i))
+                .map(|i| (Some(i), None)),
+        );
+        ret.extend(
+            (j..next_b)
+                .filter(|j| !gap_b[..paired].contains(j))
+                .map(|j| (None, Some(j))),
+        );
+        if next_a < end_a {
+            ret.push((Some(next_a), Some(next_b)));
+        }
+        (i, j) = (next_a + 1, next_b + 1);
+    }
+    ret.extend(
+        (end_a..a.len())
+            .zip(end_b..b.len())
+            .map(|(i, j)| (Some(i), Some(j))),
+    );
+    ret
+}
+
+struct Differ {
+    rows: Vec<Row>,
+    /// Whether to leave out unchanged rows, and skip over nodes that are identical.
+    pruned: bool,
+}
+
+impl Differ {
+    /// A node on both sides, and what's below it.
+    fn both(&mut self, paths: [Vec<String>; 2], [a, b]: [&Value; 2], depth: usize, moved: bool) {
+        let skip = self.pruned && identical(a, b);
+        if skip && !moved {
+            return;
+        }
+        let change = if discriminant(a) != discriminant(b) {
+            Change::TypeChanged
+        } else if a != b {
+            Change::Changed
+        } else if moved {
+            Change::Moved
+        } else {
+            Change::Same
+        };
+        if !self.pruned || change != Change::Same {
+            self.rows.push(Row {
+                paths: paths.clone(),
+                present: [true, true],
+                depth,
+                change,
+                inherited: false,
+            });
+        }
+        if skip {
+            return;
+        }
+        match (a, b) {
+            (Value::Dictionary(a), Value::Dictionary(b)) => self.dict(&paths, [a, b], depth + 1),
+            (Value::Array(a), Value::Array(b)) => self.array(&paths, [a, b], depth + 1),
+            _ if change == Change::TypeChanged => {
+                self.below(Side::Left, &paths[0], a, depth + 1, Change::Removed);
+                self.below(Side::Right, &paths[1], b, depth + 1, Change::Added);
+            }
+            _ => {}
+        }
+    }
+
+    /// A node on `side` only, and what's below it. `slot` is where it would go on the other.
+    fn one(&mut self, side: Side, path: Vec<String>, slot: Vec<String>, v: &Value, depth: usize) {
+        let change = match side {
+            Side::Left => Change::Removed,
+            Side::Right => Change::Added,
+        };
+        let mut paths = [path, slot];
+        let mut present = [true, false];
+        if side == Side::Right {
+            paths.reverse();
+            present.reverse();
+        }
+        self.rows.push(Row {
+            paths: paths.clone(),
+            present,
+            depth,
+            change,
+            inherited: false,
+        });
+        self.below(side, &paths[side.index()], v, depth + 1, change);
+    }
+
+    /// Everything below a node on `side` that has no counterpart to line up with.
+    fn below(&mut self, side: Side, path: &[String], v: &Value, depth: usize, change: Change) {
+        let children: Vec<(String, &Value)> = match v {
+            Value::Dictionary(v) => v.iter().map(|(k, v)| (k.clone(), v)).collect(),
+            Value::Array(v) => v
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i.to_string(), v))
+                .collect(),
+            _ => return,
+        };
+        for (k, v) in children {
+            let path = child(path, k);
+            let mut present = [false; 2];
+            present[side.index()] = true;
+            self.rows.push(Row {
+                paths: [path.clone(), path.clone()],
+                present,
+                depth,
+                change,
+                inherited: true,
+            });
+            self.below(side, &path, v, depth + 1, change);
+        }
+    }
+
+    /// The entries of two dictionaries in the left's order, with entries only on the right
+    /// placed after the entry they follow there.
+    fn dict(&mut self, paths: &[Vec<String>; 2], [a, b]: [&Dictionary; 2], depth: usize) {
+        let common_a = a.keys().filter(|k| b.contains_key(k)).collect::<Vec<_>>();
+        let common_b = b.keys().filter(|k| a.contains_key(k)).collect::<Vec<_>>();
+        let kept = lcs(&common_a, &common_b)
+            .into_iter()
+            .map(|(i, _)| common_a[i])
+            .collect::<HashSet<_>>();
+        let keys_b = b.keys().collect::<Vec<_>>();
+        let mut next_b = 0;
+        for (k, v) in a {
+            let Some(w) = b.get(k) else {
+                self.one(
+                    Side::Left,
+                    child(&paths[0], k),
+                    child(&paths[1], k),
+                    v,
+                    depth,
+                );
+                continue;
+            };
+            if kept.contains(k) {
+                let end = keys_b.iter().position(|v| *v == k).unwrap();
+                self.only_right(paths, a, &keys_b[next_b..end], b, depth);
+                next_b = end + 1;
+            }
+            self.both(
+                [child(&paths[0], k), child(&paths[1], k)],
+                [v, w],
+                depth,
+                !kept.contains(k),
+            );
+        }
+        self.only_right(paths, a, &keys_b[next_b..], b, depth);
+    }
+
+    fn only_right(
+        &mut self,
+        paths: &[Vec<String>; 2],
+        a: &Dictionary,
+        keys: &[&String],
+        b: &Dictionary,
+        depth: usize,
+    ) {
+        for k in keys.iter().filter(|k| !a.contains_key(k)) {
+            self.one(
+                Side::Right,
+                child(&paths[1], *k),
+                child(&paths[0], *k),
+                &b[*k],
+                depth,
+            );
+        }
+    }
+
+    fn array(&mut self, paths: &[Vec<String>; 2], [a, b]: [&Vec<Value>; 2], depth: usize) {
+        // The index each side is up to, which is where an item missing from it would go.
+        let (mut next_a, mut next_b) = (0, 0);
+        for pair in align(a, b) {
+            match pair {
+                (Some(i), Some(j)) => {
+                    self.both(
+                        [
+                            child(&paths[0], i.to_string()),
+                            child(&paths[1], j.to_string()),
+                        ],
+                        [&a[i], &b[j]],
+                        depth,
+                        false,
+                    );
+                    (next_a, next_b) = (i + 1, j + 1);
+                }
+                (Some(i), None) => {
+                    let slot = child(&paths[1], next_b.to_string());
+                    self.one(
+                        Side::Left,
+                        child(&paths[0], i.to_string()),
+                        slot,
+                        &a[i],
+                        depth,
+                    );
+                    next_a = i + 1;
+                }
+                (None, Some(j)) => {
+                    let slot = child(&paths[0], next_a.to_string());
+                    self.one(
+                        Side::Right,
+                        child(&paths[1], j.to_string()),
+                        slot,
+                        &b[j],
+                        depth,
+                    );
+                    next_b = j + 1;
+                }
+                (None, None) => {}
+            }
+        }
+    }
+
+    /// Pairs up nodes removed from one place and added unchanged somewhere else: array items,
+    /// or dictionary entries under the same key.
+    fn find_moves(&mut self, roots: [&Value; 2]) {
+        let in_array = |side: Side, path: &[String]| {
+            path.split_last()
+                .and_then(|(_, parent)| try_pv(parent, roots[side.index()]))
+                .is_some_and(|v| v.as_array().is_some())
+        };
+        let value = |row: &Row, side: Side| try_pv(&row.paths[side.index()], roots[side.index()]);
+        let tops = |change| {
+            (0..self.rows.len())
+                .filter(|&i| !self.rows[i].inherited && self.rows[i].change == change)
+                .collect::<Vec<_>>()
+        };
+        let mut added = tops(Change::Added);
+        for i in tops(Change::Removed) {
+            let removed = &self.rows[i];
+            let item = in_array(Side::Left, &removed.paths[0]);
+            let Some(pos) = added.iter().position(|&j| {
+                let row = &self.rows[j];
+                let matches = if item {
+                    in_array(Side::Right, &row.paths[1])
+                } else {
+                    !in_array(Side::Right, &row.paths[1])
+                        && row.paths[1].last() == removed.paths[0].last()
+                };
+                matches && value(row, Side::Right) == value(removed, Side::Left)
+            }) else {
+                continue;
+            };
+            let j = added.remove(pos);
+            self.mark_moved(i);
+            self.mark_moved(j);
+        }
+    }
+
+    fn mark_moved(&mut self, i: usize) {
+        let depth = self.rows[i].depth;
+        self.rows[i].change = Change::Moved;
+        for row in self.rows[i + 1..]
+            .iter_mut()
+            .take_while(|v| v.depth > depth && v.inherited)
+        {
+            row.change = Change::Moved;
+        }
+    }
+}
+
+fn run(left: &Value, right: &Value, pruned: bool) -> Vec<Row> {
+    let mut differ = Differ {
+        rows: Vec::new(),
+        pruned,
+    };
+    differ.both([Vec::new(), Vec::new()], [left, right], 0, false);
+    differ.find_moves([left, right]);
+    differ.rows
+}
+
+/// Lines up two documents node by node, in the order rows are laid out.
+#[must_use]
+pub fn diff(left: &Value, right: &Value) -> Vec<Row> {
+    run(left, right, false)
+}
+
+/// Like [`diff`], but only the rows that changed, which is much quicker when few did.
+#[must_use]
+pub fn changes(left: &Value, right: &Value) -> Vec<Row> {
+    run(left, right, true)
+}
+
+/// A copy of `target` with `key` set to `value`, placed after the last key that comes before it
+/// in `source` and is also in `target`.
+fn placed(target: &Dictionary, key: &str, value: Value, source: &Dictionary) -> Dictionary {
+    let after = source
+        .keys()
+        .take_while(|v| *v != key)
+        .filter(|v| target.contains_key(v))
+        .last();
+    let mut value = Some(value);
+    let mut ret = Dictionary::new();
+    if after.is_none() {
+        ret.insert(key.to_owned(), value.take().unwrap());
+    }
+    for (k, v) in target.iter().filter(|(k, _)| *k != key) {
+        ret.insert(k.clone(), v.clone());
+        if Some(k) == after {
+            ret.insert(key.to_owned(), value.take().unwrap());
+        }
+    }
+    ret
+}
+
+/// The edit that makes the `to` side of `row` match the other one.
+#[must_use]
+pub fn copy(row: &Row, to: Side, roots: [&Value; 2]) -> Option<Command> {
+    if !row.copyable() {
+        return None;
+    }
+    let from = to.other();
+    let (src, dst) = (&row.paths[from.index()], &row.paths[to.index()]);
+    if !row.present[from.index()] {
+        return Some(Command::Remove { path: dst.clone() });
+    }
+    let value = try_pv(src, roots[from.index()])?.clone();
+    let Some((k, parent)) = dst.split_last() else {
+        return Some(Command::Set {
+            path: Vec::new(),
+            value,
+        });
+    };
+    let in_place = row.present[to.index()] && row.change != Change::Moved;
+    match try_pv(parent, roots[to.index()])? {
+        // Entries are put in the same place among their siblings, which Insert can't do.
+        Value::Dictionary(target) if !in_place => {
+            let source = try_pv(&src[..src.len() - 1], roots[from.index()])?.as_dictionary()?;
+            Some(Command::Set {
+                path: parent.to_vec(),
+                value: Value::Dictionary(placed(target, k, value, source)),
+            })
+        }
+        Value::Array(_) if !row.present[to.index()] => Some(Command::Insert {
+            path: dst.clone(),
+            value,
+        }),
+        _ => Some(Command::Set {
+            path: dst.clone(),
+            value,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plist::{Dictionary, Value};
+
+    use super::{Change, Row, Side, changes, copy};
+    use crate::{document::Command, utils::identical};
+
+    fn dict<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Dictionary(Dictionary::from_iter(entries))
+    }
+
+    fn array<const N: usize>(items: [Value; N]) -> Value {
+        Value::Array(items.into())
+    }
+
+    /// The changed rows, with the path on each side joined by slashes.
+    fn changed(left: &Value, right: &Value) -> Vec<(Change, String, String)> {
+        changes(left, right)
+            .into_iter()
+            .map(|v| (v.change, v.paths[0].join("/"), v.paths[1].join("/")))
+            .collect()
+    }
+
+    fn row(change: Change, left: &str, right: &str) -> (Change, String, String) {
+        (change, left.to_owned(), right.to_owned())
+    }
+
+    /// Copies changes to the `to` side one at a time until there are none left, checking the
+    /// two sides end up identical.
+    fn copy_all(left: &Value, right: &Value, to: Side) {
+        let mut roots = [left.clone(), right.clone()];
+        for _ in 0..20 {
+            let rows = changes(&roots[0], &roots[1]);
+            let Some(row) = rows
+                .iter()
+                .find(|v| v.copyable() && !v.is_ancestor([&roots[0], &roots[1]]))
+            else {
+                assert!(identical(&roots[0], &roots[1]));
+                return;
+            };
+            let command = copy(row, to, [&roots[0], &roots[1]]).expect("row should copy");
+            command
+                .apply(&mut roots[to.index()])
+                .expect("copy should change something");
+        }
+        panic!("copying didn't settle: {roots:?}");
+    }
+
+    fn dicts() -> (Value, Value) {
+        let left = dict([
+            ("same", 1.into()),
+            ("moved", 2.into()),
+            ("changed", 3.into()),
+            ("retyped", 4.into()),
+            ("removed", 5.into()),
+            ("p", dict([("x", 7.into())])),
+            ("q", dict([])),
+        ]);
+        let right = dict([
+            ("same", 1.into()),
+            ("changed", 30.into()),
+            ("retyped", "4".into()),
+            ("moved", 2.into()),
+            ("added", 6.into()),
+            ("p", dict([])),
+            ("q", dict([("x", 7.into())])),
+        ]);
+        (left, right)
+    }
+
+    #[test]
+    fn dict_changes() {
+        let (left, right) = dicts();
+        assert_eq!(
+            changed(&left, &right),
+            [
+                row(Change::Changed, "", ""),
+                row(Change::Moved, "moved", "moved"),
+                row(Change::Changed, "changed", "changed"),
+                row(Change::TypeChanged, "retyped", "retyped"),
+                row(Change::Removed, "removed", "removed"),
+                row(Change::Added, "added", "added"),
+                row(Change::Changed, "p", "p"),
+                row(Change::Moved, "p/x", "p/x"),
+                row(Change::Changed, "q", "q"),
+                row(Change::Moved, "q/x", "q/x"),
+            ]
+        );
+        assert!(changes(&left, &left).is_empty());
+    }
+
+    #[test]
+    fn array_changes() {
+        let left = array([1.into(), 2.into(), 3.into(), 4.into(), 5.into()]);
+        let right = array([1.into(), 3.into(), "4".into(), 2.into(), 6.into(), 5.into()]);
+        assert_eq!(
+            changed(&left, &right),
+            [
+                row(Change::Changed, "", ""),
+                row(Change::Moved, "1", "1"),
+                row(Change::TypeChanged, "3", "2"),
+                row(Change::Moved, "4", "3"),
+                row(Change::Added, "4", "4"),
+            ]
+        );
+
+        let shorter = array([1.into(), 9.into()]);
+        assert_eq!(
+            changed(&left, &shorter),
+            [
+                row(Change::Changed, "", ""),
+                row(Change::Changed, "1", "1"),
+                row(Change::Removed, "2", "2"),
+                row(Change::Removed, "3", "2"),
+                row(Change::Removed, "4", "2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn copying() {
+        let (left, right) = dicts();
+        copy_all(&left, &right, Side::Left);
+        copy_all(&left, &right, Side::Right);
+
+        let left = array([1.into(), 2.into(), 3.into(), 4.into(), 5.into()]);
+        let right = array([1.into(), 3.into(), "4".into(), 2.into(), 6.into(), 5.into()]);
+        copy_all(&left, &right, Side::Left);
+        copy_all(&left, &right, Side::Right);
+
+        let rows = changes(&left, &right);
+        let added = rows.iter().find(|v| v.change == Change::Added).unwrap();
+        assert_eq!(
+            copy(added, Side::Left, [&left, &right]),
+            Some(Command::Insert {
+                path: vec!["4".into()],
+                value: 6.into(),
+            })
+        );
+        assert_eq!(
+            copy(added, Side::Right, [&left, &right]),
+            Some(Command::Remove {
+                path: vec!["4".into()],
+            })
+        );
+        let inherited = Row {
+            inherited: true,
+            ..added.clone()
+        };
+        assert_eq!(copy(&inherited, Side::Left, [&left, &right]), None);
+    }
+}
@@ -0,0 +1,630 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::sync::Arc;
+
+use plist::Value;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    format::Format,
+    history::History,
+    utils::{identical, rename_key, try_pv_mut, unique_key},
+};
+
+/// An edit to a document. Applying one gives back the command that undoes it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Replaces the value at `path`.
+    Set { path: Vec<String>, value: Value },
+    /// Adds `value` at `path`: at that index of an array, clamped to its length, or under that
+    /// key of a dictionary, made unique if it's taken. New dictionary entries go last.
+    Insert { path: Vec<String>, value: Value },
+    /// Removes the value at `path`. In a dictionary the last entry takes its place.
+    Remove { path: Vec<String> },
+    /// Puts a dictionary entry back at `index`, moving the entry there to the end, which undoes
+    /// a [`Remove`](Self::Remove).
+    Restore {
+        path: Vec<String>,
+        index: usize,
+        value: Value,
+    },
+    /// Renames the dictionary entry at `path` to `key`, keeping its place.
+    Rename { path: Vec<String>, key: String },
+    /// Applies `command` to the plist held in the Data value at `path`, encoding it back the way
+    /// it was read.
+    Nested {
+        path: Vec<String>,
+        command: Box<Self>,
+    },
+    /// Applies each command in turn, undone as one.
+    Batch(Vec<Self>),
+}
+
+impl Command {
+    /// Wraps `command` so it applies to the document reached through each Data value in
+    /// `nesting`, outermost first.
+    #[must_use]
+    pub fn nested(nesting: &[Vec<String>], command: Self) -> Self {
+        nesting
+            .iter()
+            .rev()
+            .fold(command, |command, path| Self::Nested {
+                path: path.clone(),
+                command: Box::new(command),
+            })
+    }
+
+    /// A batch of `commands`, or `None` if there aren't any.
+    #[must_use]
+    pub fn batch(commands: Vec<Self>) -> Option<Self> {
+        (!commands.is_empty()).then_some(Self::Batch(commands))
+    }
+
+    /// Applies the command to `root`, returning the command that undoes it, or `None` if it
+    /// changed nothing, such as when its path no longer holds.
+    pub fn apply(self, root: &mut Value) -> Option<Self> {
+        match self {
+            Self::Set { path, value } => {
+                let slot = try_pv_mut(&path, root)?;
+                // Reordering a dictionary is a change, though `==` doesn't see it.
+                if identical(slot, &value) {
+                    return None;
+                }
+                let value = std::mem::replace(slot, value);
+                Some(Self::Set { path, value })
+            }
+            Self::Insert { mut path, value } => {
+                let k = path.pop()?;
+                let key = match try_pv_mut(&path, root)? {
+                    Value::Dictionary(v) => {
+                        let key = unique_key(v, &k);
+                        v.insert(key.clone(), value);
+                        key
+                    }
+                    Value::Array(v) => {
+                        let i = k.parse::<usize>().ok()?.min(v.len());
+                        v.insert(i, value);
+                        i.to_string()
+                    }
+                    _ => return None,
+                };
+                path.push(key);
+                Some(Self::Remove { path })
+            }
+            Self::Remove { path } => {
+                let (k, parent) = path.split_last()?;
+                match try_pv_mut(parent, root)? {
+                    Value::Dictionary(v) => {
+                        let index = v.keys().position(|v| v == k)?;
+                        let value = v.remove(k)?;
+                        Some(Self::Restore { path, index, value })
+                    }
+                    Value::Array(v) => {
+                        let i = k.parse::<usize>().ok().filter(|&i| i < v.len())?;
+                        let value = v.remove(i);
+                        Some(Self::Insert { path, value })
+                    }
+                    _ => None,
+                }
+            }
+            Self::Restore { path, index, value } => {
+                let (k, parent) = path.split_last()?;
+                let Value::Dictionary(v) = try_pv_mut(parent, root)? else {
+                    return None;
+                };
+                if v.contains_key(k) {
+                    return None;
+                }
+                let moved = v.keys().nth(index).cloned();
+                v.insert(k.clone(), value);
+                // Removing swaps the last entry, the one just added, into the freed slot.
+                if let Some(moved) = moved
+                    && let Some(value) = v.remove(&moved)
+                {
+                    v.insert(moved, value);
+                }
+                Some(Self::Remove { path })
+            }
+            Self::Rename { mut path, key } => {
+                let k = path.pop()?;
+                let Value::Dictionary(v) = try_pv_mut(&path, root)? else {
+                    return None;
+                };
+                if !rename_key(v, &k, key.clone()) {
+                    return None;
+                }
+                path.push(key);
+                Some(Self::Rename { path, key: k })
+            }
+            Self::Nested { path, command } => {
+                let Value::Data(bytes) = try_pv_mut(&path, root)? else {
+                    return None;
+                };
+                let format = Format::detect(bytes)?;
+                let mut nested = format.decode(bytes)?;
+                command.apply(&mut nested)?;
+                let bytes = std::mem::replace(bytes, format.encode(&nested)?);
+                // Undo restores the bytes as they were, rather than encoding them again.
+                Some(Self::Set {
+                    path,
+                    value: Value::Data(bytes),
+                })
+            }
+            Self::Batch(commands) => {
+                let mut ret = commands
+                    .into_iter()
+                    .filter_map(|v| v.apply(root))
+                    .collect::<Vec<_>>();
+                ret.reverse();
+                Self::batch(ret)
+            }
+        }
+    }
+}
+
+/// The open plist. It is only read through [`root`](Self::root) and only changed by applying
+/// [`Command`]s, which is what undo, unsaved changes and anything derived from it hang off.
+pub struct Document {
+    root: Arc<Value>,
+    /// The document as it was opened or last saved.
+    baseline: Arc<Value>,
+    history: History,
+    revision: u64,
+    settled: u64,
+    unsaved: bool,
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self::new(Value::Dictionary(plist::Dictionary::new()))
+    }
+}
+
+impl Document {
+    #[must_use]
+    pub fn new(root: Value) -> Self {
+        let root = Arc::new(root);
+        Self {
+            baseline: root.clone(),
+            root,
+            history: History::default(),
+            revision: 0,
+            settled: 0,
+            unsaved: false,
+        }
+    }
+
+    #[must_use]
+    pub fn root(&self) -> &Value {
+        &self.root
+    }
+
+    /// The document as it was opened or last saved, which changes are shown against.
+    #[must_use]
+    pub const fn baseline(&self) -> &Arc<Value> {
+        &self.baseline
+    }
+
+    /// Counts changes to the document, so whatever is worked out from it can tell when it's out
+    /// of date.
+    #[must_use]
+    pub const fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Like the revision, but only moves on once a change is done with: when its undo step is
+    /// closed, and on undo, redo, saving and opening. Work too slow to redo on every keystroke
+    /// or drag keys off this instead.
+    #[must_use]
+    pub const fn settled(&self) -> u64 {
+        self.settled
+    }
+
+    #[must_use]
+    pub const fn is_unsaved(&self) -> bool {
+        self.unsaved
+    }
+
+    /// Makes the document as it is the baseline. The revision moves on, since what's shown as
+    /// changed does.
+    pub fn mark_saved(&mut self) {
+        self.baseline = self.root.clone();
+        self.revision += 1;
+        self.settled += 1;
+        self.unsaved = false;
+    }
+
+    /// Replaces the document with a newly opened one, forgetting the undo history.
+    pub fn replace(&mut self, root: Value) {
+        self.root = Arc::new(root);
+        self.baseline = self.root.clone();
+        self.history = History::default();
+        self.revision += 1;
+        self.settled += 1;
+        self.unsaved = false;
+    }
+
+    const fn changed(&mut self) {
+        self.revision += 1;
+        self.unsaved = true;
+    }
+
+    /// Applies `command`, adding it to the open undo step. Returns whether anything changed.
+    pub fn apply(&mut self, command: Command) -> bool {
+        let Some(inverse) = command.apply(Arc::make_mut(&mut self.root)) else {
+            return false;
+        };
+        self.history.record(inverse);
+        self.changed();
+        true
+    }
+
+    /// Closes the open undo step, so later edits undo separately.
+    pub fn commit(&mut self) {
+        if self.history.commit() {
+            self.settled += 1;
+        }
+    }
+
+    #[must_use]
+    pub const fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    #[must_use]
+    pub const fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let ret = self.history.undo(Arc::make_mut(&mut self.root));
+        if ret {
+            self.changed();
+            self.settled += 1;
+        }
+        ret
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let ret = self.history.redo(Arc::make_mut(&mut self.root));
+        if ret {
+            self.changed();
+            self.settled += 1;
+        }
+        ret
+    }
+}
+
+/// What outlives the app: the value, whether it's unsaved, and if so what it was saved as. The
+/// undo history starts afresh.
+#[derive(Serialize, Deserialize)]
+struct Persisted<V> {
+    root: V,
+    unsaved: bool,
+    baseline: Option<V>,
+}
+
+impl Serialize for Document {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Persisted {
+            root: self.root(),
+            unsaved: self.unsaved,
+            baseline: self.unsaved.then(|| self.baseline.as_ref()),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Document {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let Persisted {
+            root,
+            unsaved,
+            baseline,
+        } = Persisted::<Value>::deserialize(deserializer)?;
+        let mut ret = Self::new(root);
+        if let Some(baseline) = baseline {
+            ret.baseline = Arc::new(baseline);
+        }
+        ret.unsaved = unsaved;
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plist::{Dictionary, Value};
+
+    use super::{Command, Document};
+    use crate::format::Format;
+
+    fn path(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|&v| v.to_owned()).collect()
+    }
+
+    fn dict(keys: &[&str]) -> Value {
+        Value::Dictionary(Dictionary::from_iter(
+            keys.iter().zip(0..).map(|(&k, i)| (k, Value::from(i))),
+        ))
+    }
+
+    /// The keys of the dictionary at `path`, in order. Dictionaries compare equal whatever order
+    /// their keys are in, so this is what shows the order was put back.
+    fn keys(root: &Value, path: &[String]) -> Vec<String> {
+        crate::utils::try_pv(path, root)
+            .and_then(Value::as_dictionary)
+            .map(|v| v.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Applies `command` to `root`, undoes it and redoes it, checking each step leaves the
+    /// dictionaries in the order they were in. Returns the inverse and the changed value.
+    fn round_trip(root: &Value, command: Command, changed: &[String]) -> (Command, Value) {
+        let mut value = root.clone();
+        let inverse = command.apply(&mut value).expect("command should apply");
+        assert_ne!(value, *root);
+        let after = keys(&value, changed);
+
+        let mut undone = value.clone();
+        let redo = inverse
+            .clone()
+            .apply(&mut undone)
+            .expect("inverse should apply");
+        assert_eq!(undone, *root);
+        assert_eq!(keys(&undone, changed), keys(root, changed));
+
+        redo.apply(&mut undone).expect("redo should apply");
+        assert_eq!(undone, value);
+        assert_eq!(keys(&undone, changed), after);
+        (inverse, value)
+    }
+
+    #[test]
+    fn set() {
+        let root = dict(&["a", "b"]);
+        let (inverse, _) = round_trip(
+            &root,
+            Command::Set {
+                path: path(&["a"]),
+                value: Value::from("x"),
+            },
+            &[],
+        );
+        assert_eq!(
+            inverse,
+            Command::Set {
+                path: path(&["a"]),
+                value: Value::from(0),
+            }
+        );
+
+        let mut value = root;
+        let same = Command::Set {
+            path: path(&["b"]),
+            value: Value::from(1),
+        };
+        assert_eq!(same.apply(&mut value), None);
+        let missing = Command::Set {
+            path: path(&["c", "d"]),
+            value: Value::from(1),
+        };
+        assert_eq!(missing.apply(&mut value), None);
+    }
+
+    #[test]
+    fn remove_and_restore() {
+        let root = dict(&["a", "b", "c", "d"]);
+        for (i, k) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            let (inverse, value) = round_trip(&root, Command::Remove { path: path(&[k]) }, &[]);
+            assert_eq!(
+                inverse,
+                Command::Restore {
+                    path: path(&[k]),
+                    index: i,
+                    value: Value::from(i as i64),
+                }
+            );
+            // The last entry is swapped into the freed slot.
+            let mut expected = path(&["a", "b", "c", "d"]);
+            expected.swap_remove(i);
+            assert_eq!(keys(&value, &[]), expected);
+        }
+
+        let mut value = root;
+        let taken = Command::Restore {
+            path: path(&["a"]),
+            index: 0,
+            value: Value::from(9),
+        };
+        assert_eq!(taken.apply(&mut value), None);
+    }
+
+    #[test]
+    fn remove_from_array() {
+        let root = Value::Array(vec![Value::from(0), Value::from(1), Value::from(2)]);
+        let (inverse, value) = round_trip(&root, Command::Remove { path: path(&["1"]) }, &[]);
+        assert_eq!(
+            inverse,
+            Command::Insert {
+                path: path(&["1"]),
+                value: Value::from(1),
+            }
+        );
+        assert_eq!(value, Value::Array(vec![Value::from(0), Value::from(2)]));
+
+        let mut value = root;
+        assert_eq!(
+            Command::Remove { path: path(&["3"]) }.apply(&mut value),
+            None
+        );
+    }
+
+    #[test]
+    fn rename() {
+        let root = dict(&["a", "b", "c"]);
+        let (inverse, value) = round_trip(
+            &root,
+            Command::Rename {
+                path: path(&["a"]),
+                key: "z".to_owned(),
+            },
+            &[],
+        );
+        assert_eq!(keys(&value, &[]), path(&["z", "b", "c"]));
+        assert_eq!(
+            inverse,
+            Command::Rename {
+                path: path(&["z"]),
+                key: "a".to_owned(),
+            }
+        );
+
+        let mut value = root;
+        let taken = Command::Rename {
+            path: path(&["a"]),
+            key: "b".to_owned(),
+        };
+        assert_eq!(taken.apply(&mut value), None);
+    }
+
+    #[test]
+    fn insert() {
+        let root = dict(&["a", "b"]);
+        let (inverse, value) = round_trip(
+            &root,
+            Command::Insert {
+                path: path(&["a"]),
+                value: Value::from("x"),
+            },
+            &[],
+        );
+        assert_eq!(
+            inverse,
+            Command::Remove {
+                path: path(&["a Duplicate"]),
+            }
+        );
+        assert_eq!(keys(&value, &[]), path(&["a", "b", "a Duplicate"]));
+
+        let root = Value::Array(vec![Value::from(0), Value::from(1)]);
+        let (inverse, value) = round_trip(
+            &root,
+            Command::Insert {
+                path: path(&["9"]),
+                value: Value::from(2),
+            },
+            &[],
+        );
+        assert_eq!(inverse, Command::Remove { path: path(&["2"]) });
+        assert_eq!(
+            value,
+            Value::Array(vec![Value::from(0), Value::from(1), Value::from(2)])
+        );
+
+        let mut value = root;
+        let scalar = Command::Insert {
+            path: path(&["0", "a"]),
+            value: Value::from(2),
+        };
+        assert_eq!(scalar.apply(&mut value), None);
+    }
+
+    #[test]
+    fn nested() {
+        for format in [Format::Binary, Format::Xml] {
+            let inner = dict(&["x", "y"]);
+            let bytes = format.encode(&inner).unwrap();
+            let mut root = dict(&["a"]);
+            root.as_dictionary_mut()
+                .unwrap()
+                .insert("n".to_owned(), Value::Data(bytes.clone()));
+
+            let command = Command::nested(&[path(&["n"])], Command::Remove { path: path(&["x"]) });
+            let (inverse, value) = round_trip(&root, command, &[]);
+            // Undo puts back the bytes as they were.
+            assert_eq!(
+                inverse,
+                Command::Set {
+                    path: path(&["n"]),
+                    value: Value::Data(bytes),
+                }
+            );
+            let Some(Value::Data(changed)) = value.as_dictionary().unwrap().get("n") else {
+                panic!("nested value should still be data");
+            };
+            assert_eq!(Format::detect(changed), Some(format));
+            let expected = Dictionary::from_iter([("y", Value::from(1))]);
+            assert_eq!(format.decode(changed), Some(Value::Dictionary(expected)));
+        }
+    }
+
+    #[test]
+    fn batch() {
+        let root = dict(&["a", "b", "c", "d"]);
+        let command = Command::Batch(vec![
+            Command::Remove { path: path(&["a"]) },
+            Command::Rename {
+                path: path(&["c"]),
+                key: "z".to_owned(),
+            },
+            Command::Insert {
+                path: path(&["a"]),
+                value: Value::from("x"),
+            },
+            // Already gone, so left out of the inverse.
+            Command::Remove {
+                path: path(&["missing"]),
+            },
+        ]);
+        let (inverse, value) = round_trip(&root, command, &[]);
+        assert_eq!(keys(&value, &[]), path(&["d", "b", "z", "a"]));
+        let Command::Batch(commands) = inverse else {
+            panic!("inverse of a batch should be a batch");
+        };
+        assert_eq!(
+            commands,
+            vec![
+                Command::Remove { path: path(&["a"]) },
+                Command::Rename {
+                    path: path(&["z"]),
+                    key: "c".to_owned(),
+                },
+                Command::Restore {
+                    path: path(&["a"]),
+                    index: 0,
+                    value: Value::from(0),
+                },
+            ]
+        );
+
+        let mut value = root;
+        assert_eq!(Command::Batch(Vec::new()).apply(&mut value), None);
+    }
+
+    #[test]
+    fn settling() {
+        let mut document = Document::new(dict(&["a", "b"]));
+        let set = |v: i32| Command::Set {
+            path: path(&["a"]),
+            value: Value::from(v),
+        };
+        assert!(document.apply(set(5)));
+        assert!(document.apply(set(6)));
+        assert_eq!((document.revision(), document.settled()), (2, 0));
+        document.commit();
+        assert_eq!(document.settled(), 1);
+        document.commit();
+        assert_eq!(document.settled(), 1);
+
+        assert!(document.undo());
+        assert_eq!(document.root(), &dict(&["a", "b"]));
+        assert_eq!(document.settled(), 2);
+        assert!(document.redo());
+        assert_eq!(document.settled(), 3);
+        document.mark_saved();
+        assert_eq!(document.settled(), 4);
+        document.replace(dict(&["c"]));
+        assert_eq!((document.revision(), document.settled()), (6, 5));
+    }
+}
@@ -0,0 +1,241 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::collections::HashSet;
+
+use egui::{ComboBox, TextEdit, Ui};
+use plist::Value;
+use regex::{Regex, RegexBuilder};
+
+use crate::{document::Document, find::value_text, utils::ValueType};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Mode {
+    #[default]
+    Key,
+    Value,
+    Type,
+    KeyPath,
+}
+
+impl Mode {
+    const ALL: [Self; 4] = [Self::Key, Self::Value, Self::Type, Self::KeyPath];
+
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Key => "Key",
+            Self::Value => "Value",
+            Self::Type => "Type",
+            Self::KeyPath => "Key Path",
+        }
+    }
+
+    const fn hint(self) -> &'static str {
+        match self {
+            Self::Key => "Key, with * and ? wildcards",
+            Self::Value => "Text in the value",
+            Self::Type => "",
+            Self::KeyPath => "a/*/b, with ** for any depth",
+        }
+    }
+}
+
+/// Matches `*` and `?` wildcards against a whole string, ignoring case.
+fn glob(pattern: &str) -> Regex {
+    let pattern = pattern
+        .split('*')
+        .map(|v| {
+            v.split('?')
+                .map(regex::escape)
+                .collect::<Vec<_>>()
+                .join(".")
+        })
+        .collect::<Vec<_>>()
+        .join(".*");
+    RegexBuilder::new(&format!("^{pattern}$"))
+        .case_insensitive(true)
+        .build()
+        .unwrap()
+}
+
+enum Segment {
+    /// `**`, matching any number of keys.
+    Any,
+    Glob(Regex),
+}
+
+fn path_matches(pattern: &[Segment], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((Segment::Any, rest)) => (0..=path.len()).any(|i| path_matches(rest, &path[i..])),
+        Some((Segment::Glob(re), rest)) => path
+            .split_first()
+            .is_some_and(|(k, path)| re.is_match(k) && path_matches(rest, path)),
+    }
+}
+
+/// A test for a single node, given its path and value.
+enum Matcher {
+    Key(Regex),
+    Value(String),
+    Type(ValueType),
+    KeyPath(Vec<Segment>),
+}
+
+impl Matcher {
+    fn matches(&self, path: &[String], value: &Value) -> bool {
+        match self {
+            Self::Key(re) => path.last().is_some_and(|v| re.is_match(v)),
+            Self::Value(s) => {
+                let text = match value {
+                    Value::Boolean(v) => Some(v.to_string()),
+                    Value::Date(v) => Some(v.to_xml_format()),
+                    v => value_text(v),
+                };
+                text.is_some_and(|v| v.to_lowercase().contains(s))
+            }
+            Self::Type(ty) => ValueType::from_val(&[], value) == *ty,
+            Self::KeyPath(pattern) => path_matches(pattern, path),
+        }
+    }
+}
+
+/// Adds every matching node under `p`, along with its ancestors, to `visible`. Returns the
+/// number of matches.
+fn collect(
+    matcher: &Matcher,
+    path: &mut Vec<String>,
+    p: &Value,
+    visible: &mut HashSet<Vec<String>>,
+) -> usize {
+    let mut count = 0;
+    if matcher.matches(path, p) {
+        count += 1;
+        for i in 0..=path.len() {
+            visible.insert(path[..i].to_vec());
+        }
+    }
+    let children: Box<dyn Iterator<Item = (String, &Value)>> = match p {
+        Value::Dictionary(v) => Box::new(v.iter().map(|(k, v)| (k.clone(), v))),
+        Value::Array(v) => Box::new(v.iter().enumerate().map(|(i, v)| (i.to_string(), v))),
+        _ => return count,
+    };
+    for (k, v) in children {
+        path.push(k);
+        count += collect(matcher, path, v, visible);
+        path.pop();
+    }
+    count
+}
+
+/// Hides every row but the matching ones and their ancestors.
+#[derive(Default)]
+pub struct Filter {
+    mode: Mode,
+    query: String,
+    ty: Option<ValueType>,
+    visible: Option<HashSet<Vec<String>>>,
+    count: usize,
+    stale: bool,
+    /// The document revision the visible rows were worked out for.
+    revision: u64,
+}
+
+impl Filter {
+    /// The rows left to show, or `None` when nothing is being filtered.
+    #[must_use]
+    pub const fn visible(&self) -> Option<&HashSet<Vec<String>>> {
+        self.visible.as_ref()
+    }
+
+    fn matcher(&self) -> Option<Matcher> {
+        let query = self.query.trim();
+        match self.mode {
+            Mode::Type => self.ty.map(Matcher::Type),
+            _ if query.is_empty() => None,
+            Mode::Key => Some(Matcher::Key(glob(query))),
+            Mode::Value => Some(Matcher::Value(query.to_lowercase())),
+            Mode::KeyPath => Some(Matcher::KeyPath(
+                query
+                    .trim_start_matches('/')
+                    .split('/')
+                    .map(|v| {
+                        if v == "**" {
+                            Segment::Any
+                        } else {
+                            Segment::Glob(glob(v))
+                        }
+                    })
+                    .collect(),
+            )),
+        }
+    }
+
+    fn refresh(&mut self, document: &Document) {
+        self.stale = false;
+        self.revision = document.revision();
+        self.count = 0;
+        self.visible = self.matcher().map(|matcher| {
+            let mut visible = HashSet::new();
+            self.count = collect(&matcher, &mut Vec::new(), document.root(), &mut visible);
+            visible
+        });
+    }
+
+    /// Shows the filter bar. Returns whether the visible rows changed.
+    pub fn ui(&mut self, ui: &mut Ui, document: &Document) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Filter");
+            let prev = (self.mode, self.ty);
+            ComboBox::from_id_salt("filter_mode")
+                .selected_text(self.mode.label())
+                .show_ui(ui, |ui| {
+                    for mode in Mode::ALL {
+                        ui.selectable_value(&mut self.mode, mode, mode.label());
+                    }
+                });
+            if self.mode == Mode::Type {
+                ComboBox::from_id_salt("filter_type")
+                    .selected_text(
+                        self.ty
+                            .map_or_else(|| "Any".to_owned(), |v| format!("{v:?}")),
+                    )
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.ty, None, "Any");
+                        for ty in ValueType::ALL {
+                            ui.selectable_value(&mut self.ty, Some(ty), format!("{ty:?}"));
+                        }
+                    });
+            } else if ui
+                .add(
+                    TextEdit::singleline(&mut self.query)
+                        .hint_text(self.mode.hint())
+                        .desired_width(250.0),
+                )
+                .changed()
+            {
+                self.stale = true;
+            }
+            if prev != (self.mode, self.ty) {
+                self.stale = true;
+            }
+            if self.stale || self.revision != document.revision() {
+                self.refresh(document);
+                changed = true;
+            }
+
+            if self.visible.is_some() {
+                let s = if self.count == 1 { "" } else { "es" };
+                ui.label(format!("{} match{s}", self.count));
+                if ui.small_button("Clear").clicked() {
+                    self.query.clear();
+                    self.ty = None;
+                    self.visible = None;
+                    changed = true;
+                }
+            }
+        });
+        changed
+    }
+}
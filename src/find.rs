@@ -0,0 +1,326 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use egui::{Key, KeyboardShortcut, Modifiers, RichText, TextEdit, Ui};
+use plist::Value;
+use regex::{NoExpand, Regex, RegexBuilder};
+
+use crate::{
+    document::{Command, Document},
+    utils::{ValueType, format_real, try_pv},
+};
+
+pub const FIND_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::F);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    Key,
+    Value,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Match {
+    pub path: Vec<String>,
+    pub field: Field,
+}
+
+/// The text searched for a value. Booleans, dates and containers aren't searched.
+pub fn value_text(v: &Value) -> Option<String> {
+    match v {
+        Value::String(s) => Some(s.clone()),
+        Value::Integer(v) => Some(v.to_string()),
+        Value::Real(v) => Some(format_real(*v)),
+        Value::Data(v) => Some(hex::encode_upper(v)),
+        _ => None,
+    }
+}
+
+/// Every match under `p`, in the order rows are laid out.
+fn collect(re: &Regex, path: &mut Vec<String>, p: &Value, out: &mut Vec<Match>) {
+    if let Some(text) = value_text(p)
+        && re.is_match(&text)
+    {
+        out.push(Match {
+            path: path.clone(),
+            field: Field::Value,
+        });
+    }
+    match p {
+        Value::Dictionary(v) => {
+            for (k, v) in v {
+                path.push(k.clone());
+                if re.is_match(k) {
+                    out.push(Match {
+                        path: path.clone(),
+                        field: Field::Key,
+                    });
+                }
+                collect(re, path, v, out);
+                path.pop();
+            }
+        }
+        Value::Array(v) => {
+            for (i, v) in v.iter().enumerate() {
+                path.push(i.to_string());
+                collect(re, path, v, out);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// What the find bar asks of the rest of the app after a frame.
+#[derive(Default)]
+pub struct FindResponse {
+    /// A match to select and scroll to.
+    pub reveal: Option<Vec<String>>,
+}
+
+#[derive(Default)]
+pub struct Find {
+    open: bool,
+    focus: bool,
+    query: String,
+    replacement: String,
+    case_sensitive: bool,
+    whole_word: bool,
+    regex: bool,
+    matches: Vec<Match>,
+    current: Option<usize>,
+    stale: bool,
+    /// The document revision the matches were found in.
+    revision: u64,
+    error: Option<String>,
+}
+
+impl Find {
+    #[must_use]
+    pub const fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub const fn open(&mut self) {
+        self.open = true;
+        self.focus = true;
+        self.stale = true;
+    }
+
+    fn regex(&self) -> Result<Option<Regex>, regex::Error> {
+        if self.query.is_empty() {
+            return Ok(None);
+        }
+        let mut pattern = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        if self.whole_word {
+            pattern = format!(r"\b(?:{pattern})\b");
+        }
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .map(Some)
+    }
+
+    fn refresh(&mut self, document: &Document) {
+        let root = document.root();
+        self.stale = false;
+        self.revision = document.revision();
+        self.matches.clear();
+        self.error = None;
+        match self.regex() {
+            Ok(Some(re)) => collect(&re, &mut Vec::new(), root, &mut self.matches),
+            Ok(None) => {}
+            Err(e) => self.error = Some(e.to_string()),
+        }
+        self.current = self
+            .current
+            .filter(|_| !self.matches.is_empty())
+            .map(|v| v.min(self.matches.len() - 1));
+    }
+
+    fn step(&mut self, forward: bool) -> Option<Vec<String>> {
+        let len = self.matches.len();
+        if len == 0 {
+            return None;
+        }
+        let i = match (self.current, forward) {
+            (None, true) => 0,
+            (None, false) => len - 1,
+            (Some(i), true) => (i + 1) % len,
+            (Some(i), false) => (i + len - 1) % len,
+        };
+        self.current = Some(i);
+        Some(self.matches[i].path.clone())
+    }
+
+    fn replace_text(&self, re: &Regex, s: &str) -> String {
+        if self.regex {
+            re.replace_all(s, self.replacement.as_str()).into_owned()
+        } else {
+            re.replace_all(s, NoExpand(&self.replacement)).into_owned()
+        }
+    }
+
+    /// Replaces within one match. Values that would no longer parse as their type, and keys
+    /// that would clash with a sibling, are left alone.
+    fn replace_match(&self, re: &Regex, m: &Match, root: &Value) -> Option<Command> {
+        let value = try_pv(&m.path, root)?;
+        match m.field {
+            Field::Value => {
+                let new = value_text(value).and_then(|v| {
+                    ValueType::from_val(&[], value).parse_value(&self.replace_text(re, &v))
+                })?;
+                (new != *value).then(|| Command::Set {
+                    path: m.path.clone(),
+                    value: new,
+                })
+            }
+            Field::Key => {
+                let (k, parent) = m.path.split_last().unwrap();
+                let key = self.replace_text(re, k);
+                try_pv(parent, root)?
+                    .as_dictionary()
+                    .is_some_and(|v| !v.contains_key(&key))
+                    .then(|| Command::Rename {
+                        path: m.path.clone(),
+                        key,
+                    })
+            }
+        }
+    }
+
+    fn replace_current(&mut self, document: &mut Document) {
+        let (Ok(Some(re)), Some(i)) = (self.regex(), self.current) else {
+            return;
+        };
+        let m = self.matches[i].clone();
+        if let Some(command) = self.replace_match(&re, &m, document.root()) {
+            document.apply(command);
+        }
+        self.refresh(document);
+        // Step past the match if it survived, otherwise the next one has taken its index.
+        self.current = if self.matches.get(i) == Some(&m) {
+            Some(i)
+        } else {
+            i.checked_sub(1)
+        };
+    }
+
+    fn replace_all(&mut self, document: &mut Document) {
+        let Ok(Some(re)) = self.regex() else {
+            return;
+        };
+        // Values first, then keys from the deepest up, so no replacement moves a later match.
+        let mut matches = self.matches.clone();
+        matches.sort_by_key(|v| (v.field == Field::Key, std::cmp::Reverse(v.path.len())));
+        let commands = matches
+            .iter()
+            .filter_map(|m| self.replace_match(&re, m, document.root()))
+            .collect();
+        if let Some(command) = Command::batch(commands) {
+            document.apply(command);
+        }
+        self.current = None;
+        self.refresh(document);
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, document: &mut Document) -> FindResponse {
+        let mut ret = FindResponse::default();
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                TextEdit::singleline(&mut self.query)
+                    .hint_text("Find")
+                    .desired_width(200.0),
+            );
+            if std::mem::take(&mut self.focus) {
+                response.request_focus();
+            }
+            let mut changed = response.changed();
+            changed |= ui
+                .toggle_value(&mut self.case_sensitive, "Aa")
+                .on_hover_text("Match case")
+                .changed();
+            changed |= ui
+                .toggle_value(&mut self.whole_word, "W")
+                .on_hover_text("Match whole words")
+                .changed();
+            changed |= ui
+                .toggle_value(&mut self.regex, ".*")
+                .on_hover_text("Use a regular expression")
+                .changed();
+            if changed {
+                self.current = None;
+                self.stale = true;
+            }
+            if self.stale || self.revision != document.revision() {
+                self.refresh(document);
+            }
+
+            if let Some(e) = &self.error {
+                ui.label(RichText::new("Invalid pattern").color(ui.visuals().error_fg_color))
+                    .on_hover_text(e);
+            } else if !self.query.is_empty() {
+                ui.label(match (self.current, self.matches.len()) {
+                    (_, 0) => "No results".to_owned(),
+                    (Some(i), n) => format!("{} of {n}", i + 1),
+                    (None, n) => format!("{n} results"),
+                });
+            }
+
+            let (enter, shift, escape) = ui.input(|v| {
+                (
+                    v.key_pressed(Key::Enter),
+                    v.modifiers.shift,
+                    v.key_pressed(Key::Escape),
+                )
+            });
+            if response.lost_focus() && enter {
+                ret.reveal = self.step(!shift);
+                response.request_focus();
+            }
+            if response.lost_focus() && escape {
+                self.open = false;
+            }
+            if ui.small_button("⏶").on_hover_text("Previous").clicked() {
+                ret.reveal = self.step(false);
+            }
+            if ui.small_button("⏷").on_hover_text("Next").clicked() {
+                ret.reveal = self.step(true);
+            }
+
+            ui.separator();
+            ui.add(
+                TextEdit::singleline(&mut self.replacement)
+                    .hint_text("Replace")
+                    .desired_width(150.0),
+            );
+            let found = !self.matches.is_empty();
+            if ui
+                .add_enabled(
+                    found && self.current.is_some(),
+                    egui::Button::new("Replace"),
+                )
+                .clicked()
+            {
+                self.replace_current(document);
+                ret.reveal = self.step(true);
+            }
+            if ui
+                .add_enabled(found, egui::Button::new("Replace All"))
+                .clicked()
+            {
+                self.replace_all(document);
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.small_button("✕").on_hover_text("Close").clicked() {
+                    self.open = false;
+                }
+            });
+        });
+        ret
+    }
+}
@@ -0,0 +1,71 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::{io::Cursor, path::Path};
+
+use plist::Value;
+
+/// How a plist file, or one stored inside a Data value, is encoded. Edits are written back the
+/// same way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Binary,
+    Xml,
+}
+
+impl Format {
+    #[must_use]
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"bplist00") {
+            Some(Self::Binary)
+        } else if bytes.trim_ascii_start().starts_with(b"<?xml") {
+            Some(Self::Xml)
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Binary => "Binary plist",
+            Self::Xml => "XML plist",
+        }
+    }
+
+    #[must_use]
+    pub fn decode(self, bytes: &[u8]) -> Option<Value> {
+        match self {
+            Self::Binary => Value::from_reader(Cursor::new(bytes)).ok(),
+            Self::Xml => Value::from_reader_xml(bytes).ok(),
+        }
+    }
+
+    #[must_use]
+    pub fn encode(self, value: &Value) -> Option<Vec<u8>> {
+        let mut buf = Vec::new();
+        match self {
+            Self::Binary => value.to_writer_binary(&mut buf),
+            Self::Xml => value.to_writer_xml(&mut buf),
+        }
+        .ok()?;
+        Some(buf)
+    }
+
+    /// Reads a plist file in any encoding, along with the encoding to write it back in.
+    /// Encodings that can't be written, such as OpenStep, are written back as XML.
+    pub fn read_file(path: &Path) -> Result<(Value, Self), String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let value = Value::from_reader(Cursor::new(&bytes))
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+        Ok((value, Self::detect(&bytes).unwrap_or(Self::Xml)))
+    }
+
+    pub fn write_file(self, path: &Path, value: &Value) -> Result<(), String> {
+        match self {
+            Self::Binary => value.to_file_binary(path),
+            Self::Xml => value.to_file_xml(path),
+        }
+        .map_err(|e| e.to_string())
+    }
+}
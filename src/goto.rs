@@ -0,0 +1,384 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use egui::{
+    Context, Id, Key, KeyboardShortcut, Modifiers, Popup, PopupCloseBehavior, RichText, TextEdit,
+    Ui,
+    text::{CCursor, CCursorRange},
+    text_edit::TextEditState,
+};
+use plist::Value;
+
+use crate::{
+    key_path::{escape_key, split_keys},
+    utils::{child_keys, try_pv},
+};
+
+pub const GOTO_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::G);
+
+const MAX_COMPLETIONS: usize = 12;
+
+/// The separators a key path may be written with, in the order to try them: PlistBuddy's
+/// `:a:b`, `/a/b`, or else `a.b`, falling back to `a/b`. Dots come first since OpenCore's
+/// device paths, such as `PciRoot(0x0)/Pci(0x1b,0x0)`, are keys with slashes in them.
+fn separators(text: &str) -> &'static [char] {
+    if text.starts_with(':') {
+        &[':']
+    } else if text.starts_with('/') {
+        &['/']
+    } else {
+        &['.', '/']
+    }
+}
+
+/// Where the last separator is that isn't escaped, as [`escape_key`] does it.
+fn last_sep(sep: char, text: &str) -> Option<usize> {
+    let mut ret = None;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && sep != '/' {
+            escaped = true;
+        } else if c == sep {
+            ret = Some(i);
+        }
+    }
+    ret
+}
+
+/// Finds the node at `segments` below `path`. Keys may themselves contain the separator without
+/// it being escaped (as in `com.apple.Boot`), so the longest run of segments naming a child is
+/// tried first.
+fn resolve(sep: char, segments: &[String], path: &mut Vec<String>, root: &Value) -> bool {
+    if segments.is_empty() {
+        return true;
+    }
+    for end in (1..=segments.len()).rev() {
+        let key = segments[..end].join(&sep.to_string());
+        path.push(key);
+        if try_pv(path, root).is_some() && resolve(sep, &segments[end..], path, root) {
+            return true;
+        }
+        path.pop();
+    }
+    false
+}
+
+/// Parses a key path written with `sep`, returning it if it names a node.
+fn parse_with(sep: char, text: &str, root: &Value) -> Option<Vec<String>> {
+    let text = text.trim_start_matches(sep);
+    let segments = if text.is_empty() {
+        Vec::new()
+    } else {
+        split_keys(text, sep)
+    };
+    let mut path = Vec::new();
+    resolve(sep, &segments, &mut path, root).then_some(path)
+}
+
+/// Parses a key path in any of the supported syntaxes, returning it if it names a node.
+#[must_use]
+pub fn parse(text: &str, root: &Value) -> Option<Vec<String>> {
+    separators(text)
+        .iter()
+        .find_map(|&sep| parse_with(sep, text, root))
+}
+
+/// Children of the node before the last `sep` whose keys start with the text after it.
+fn completions_with(sep: char, text: &str, root: &Value) -> Vec<String> {
+    let (parent, partial) =
+        last_sep(sep, text).map_or(("", text), |i| (&text[..i], &text[i + 1..]));
+    let partial = split_keys(partial, sep).concat();
+    let Some(parent) = parse_with(sep, parent, root) else {
+        return Vec::new();
+    };
+    if !try_pv(&parent, root).is_some_and(|v| matches!(v, Value::Dictionary(_) | Value::Array(_))) {
+        return Vec::new();
+    }
+    let partial = partial.to_lowercase();
+    child_keys(&parent, root)
+        .into_iter()
+        .filter(|v| {
+            let v = v.to_lowercase();
+            v.starts_with(&partial) && v != partial
+        })
+        .take(MAX_COMPLETIONS)
+        .collect()
+}
+
+/// The completions for the first separator that has any, along with that separator.
+fn completions(text: &str, root: &Value) -> (char, Vec<String>) {
+    let seps = separators(text);
+    seps.iter()
+        .map(|&sep| (sep, completions_with(sep, text, root)))
+        .find(|(_, v)| !v.is_empty())
+        .unwrap_or((seps[0], Vec::new()))
+}
+
+/// Replaces the text after the last `sep` with `key`, leaving the cursor at the end.
+fn complete(ctx: &Context, id: Id, text: &mut String, sep: char, key: &str) {
+    text.truncate(last_sep(sep, text).map_or(0, |i| i + 1));
+    text.push_str(&escape_key(key, sep));
+    let mut state = TextEditState::load(ctx, id).unwrap_or_default();
+    state
+        .cursor
+        .set_char_range(Some(CCursorRange::one(CCursor::new(text.chars().count()))));
+    state.store(ctx, id);
+}
+
+#[derive(Default)]
+pub struct GoTo {
+    open: bool,
+    focus: bool,
+    text: String,
+    not_found: bool,
+}
+
+impl GoTo {
+    #[must_use]
+    pub const fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub const fn open(&mut self) {
+        self.open = true;
+        self.focus = true;
+    }
+
+    /// Returns the node to reveal once a path is entered.
+    pub fn ui(&mut self, ui: &mut Ui, root: &Value) -> Option<Vec<String>> {
+        let mut ret = None;
+        ui.horizontal(|ui| {
+            ui.label("Go to");
+            let id = Id::new("goto_field");
+            let (sep, completions) = completions(&self.text, root);
+            // Tab completes rather than moving focus.
+            let tab = ui.memory(|v| v.has_focus(id))
+                && !completions.is_empty()
+                && ui.input_mut(|v| v.consume_key(Modifiers::NONE, Key::Tab));
+            let mut response = ui.add(
+                TextEdit::singleline(&mut self.text)
+                    .id(id)
+                    .hint_text("Key path, such as Kernel.Add.0 or :Kernel:Add:0")
+                    .desired_width(350.0)
+                    .lock_focus(true),
+            );
+            if std::mem::take(&mut self.focus) {
+                response.request_focus();
+            }
+            if tab {
+                complete(ui.ctx(), id, &mut self.text, sep, &completions[0]);
+                response.mark_changed();
+            }
+            if response.changed() {
+                self.not_found = false;
+            }
+
+            Popup::from_response(&response)
+                .open(!completions.is_empty() && (response.has_focus() || response.lost_focus()))
+                .close_behavior(PopupCloseBehavior::IgnoreClicks)
+                .show(|ui| {
+                    for key in &completions {
+                        if ui.selectable_label(false, key).clicked() {
+                            complete(ui.ctx(), id, &mut self.text, sep, key);
+                            self.focus = true;
+                        }
+                    }
+                });
+
+            let (enter, escape) =
+                ui.input(|v| (v.key_pressed(Key::Enter), v.key_pressed(Key::Escape)));
+            if response.lost_focus() && enter {
+                ret = parse(&self.text, root);
+                self.not_found = ret.is_none();
+                if ret.is_some() {
+                    self.open = false;
+                } else {
+                    response.request_focus();
+                }
+            }
+            if response.lost_focus() && escape {
+                self.open = false;
+            }
+            if self.not_found {
+                ui.label(RichText::new("No such key").color(ui.visuals().error_fg_color));
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.small_button("✕").on_hover_text("Close").clicked() {
+                    self.open = false;
+                }
+            });
+        });
+        ret
+    }
+}
+
+/// Shows the path to the selected node, returning the ancestor clicked, if any.
+pub fn breadcrumb(ui: &mut Ui, path: Option<&[String]>) -> Option<Vec<String>> {
+    let mut ret = None;
+    ui.horizontal(|ui| {
+        let Some(path) = path else {
+            ui.weak("No selection");
+            return;
+        };
+        ui.spacing_mut().item_spacing.x = 2.0;
+        for i in 0..=path.len() {
+            if i != 0 {
+                ui.weak("›");
+            }
+            let name = if i == 0 { "Root" } else { &path[i - 1] };
+            if ui.selectable_label(i == path.len(), name).clicked() {
+                ret = Some(path[..i].to_vec());
+            }
+        }
+    });
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use plist::{Dictionary, Value};
+
+    use super::{completions, parse};
+    use crate::key_path::KeyPathFormat;
+
+    fn dict<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Dictionary(Dictionary::from_iter(entries))
+    }
+
+    fn path(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|&v| v.to_owned()).collect()
+    }
+
+    fn root() -> Value {
+        dict([
+            (
+                "DeviceProperties",
+                dict([(
+                    "Add",
+                    dict([
+                        (
+                            "PciRoot(0x0)/Pci(0x1b,0x0)",
+                            dict([("layout-id", Value::from(1))]),
+                        ),
+                        ("PciRoot(0x0)/Pci(0x2,0x0)", dict([])),
+                    ]),
+                )]),
+            ),
+            (
+                "Kernel",
+                dict([(
+                    "Add",
+                    Value::Array(vec![dict([("BundlePath", Value::from("Lilu.kext"))])]),
+                )]),
+            ),
+            ("com.apple.Boot", dict([("a.b", Value::from(0))])),
+            ("a:b/c~d\\e.f", dict([("x.y", Value::from(0))])),
+        ])
+    }
+
+    #[test]
+    fn syntaxes() {
+        let root = root();
+        let expected = path(&["Kernel", "Add", "0", "BundlePath"]);
+        for text in [
+            "Kernel.Add.0.BundlePath",
+            ":Kernel:Add:0:BundlePath",
+            "Kernel/Add/0/BundlePath",
+            "/Kernel/Add/0/BundlePath",
+        ] {
+            assert_eq!(parse(text, &root), Some(expected.clone()), "{text}");
+        }
+        for text in ["", ":", "/"] {
+            assert_eq!(parse(text, &root), Some(Vec::new()), "{text:?}");
+        }
+        assert_eq!(parse("Kernel.Nope", &root), None);
+        assert_eq!(parse("Kernel:Add", &root), None);
+    }
+
+    #[test]
+    fn separators_in_keys() {
+        let root = root();
+        assert_eq!(
+            parse(
+                "DeviceProperties.Add.PciRoot(0x0)/Pci(0x1b,0x0).layout-id",
+                &root
+            ),
+            Some(path(&[
+                "DeviceProperties",
+                "Add",
+                "PciRoot(0x0)/Pci(0x1b,0x0)",
+                "layout-id"
+            ]))
+        );
+        assert_eq!(
+            parse(":DeviceProperties:Add:PciRoot(0x0)/Pci(0x2,0x0)", &root),
+            Some(path(&[
+                "DeviceProperties",
+                "Add",
+                "PciRoot(0x0)/Pci(0x2,0x0)"
+            ]))
+        );
+        assert_eq!(
+            parse("com.apple.Boot.a.b", &root),
+            Some(path(&["com.apple.Boot", "a.b"]))
+        );
+        assert_eq!(
+            parse("com.apple.Boot/a.b", &root),
+            Some(path(&["com.apple.Boot", "a.b"]))
+        );
+    }
+
+    #[test]
+    fn escaped() {
+        let root = root();
+        let paths = [
+            path(&["com.apple.Boot", "a.b"]),
+            path(&["a:b/c~d\\e.f", "x.y"]),
+            path(&["DeviceProperties", "Add", "PciRoot(0x0)/Pci(0x1b,0x0)"]),
+        ];
+        let formats = [
+            KeyPathFormat::PlistBuddy,
+            KeyPathFormat::Dotted,
+            KeyPathFormat::JsonPointer,
+        ];
+        for format in formats {
+            for path in &paths {
+                let text = format.format(path, &root);
+                assert_eq!(parse(&text, &root).as_ref(), Some(path), "{text}");
+            }
+        }
+        assert_eq!(
+            completions("a:b/c~d\\\\e\\.f.x", &root),
+            ('.', path(&["x.y"]))
+        );
+        assert_eq!(
+            completions("/a:b~1c~0d\\e.f/x", &root),
+            ('/', path(&["x.y"]))
+        );
+    }
+
+    #[test]
+    fn completing() {
+        let root = root();
+        assert_eq!(
+            completions("DeviceProperties.Add.PciRoot(0x0)/Pci(0x1", &root),
+            ('.', path(&["PciRoot(0x0)/Pci(0x1b,0x0)"]))
+        );
+        assert_eq!(completions("DeviceProperties.Add.pci", &root).1.len(), 2);
+        assert_eq!(completions("Kernel/Ad", &root), ('/', path(&["Add"])));
+        assert_eq!(
+            completions(":Kernel:Add:0:B", &root),
+            (':', path(&["BundlePath"]))
+        );
+        assert_eq!(
+            completions("com.apple.B", &root).1,
+            path(&["com.apple.Boot"])
+        );
+        assert!(completions("Kernel.Add.0.BundlePath", &root).1.is_empty());
+        assert!(completions("Nope.", &root).1.is_empty());
+    }
+}
@@ -0,0 +1,72 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use egui::{Key, KeyboardShortcut, Modifiers};
+use plist::Value;
+
+use crate::document::Command;
+
+pub const UNDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+pub const REDO_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
+
+const MAX_STEPS: usize = 100;
+
+/// Undo steps, each the command that undoes one committed edit.
+///
+/// Everything applied until the next commit joins the same step, so a bulk action or a drag
+/// undoes in one go.
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Command>,
+    redo: Vec<Command>,
+    /// What undoes each command applied since the last commit, latest last.
+    open: Vec<Command>,
+}
+
+impl History {
+    /// Adds what undoes a command just applied to the open step.
+    pub fn record(&mut self, inverse: Command) {
+        self.open.push(inverse);
+        self.redo.clear();
+    }
+
+    /// Closes the open step, returning whether there was anything in it.
+    pub fn commit(&mut self) -> bool {
+        let mut step = std::mem::take(&mut self.open);
+        step.reverse();
+        let Some(step) = Command::batch(step) else {
+            return false;
+        };
+        self.undo.push(step);
+        if self.undo.len() > MAX_STEPS {
+            self.undo.remove(0);
+        }
+        true
+    }
+
+    #[must_use]
+    pub const fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || !self.open.is_empty()
+    }
+
+    #[must_use]
+    pub const fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn undo(&mut self, root: &mut Value) -> bool {
+        self.commit();
+        let Some(step) = self.undo.pop() else {
+            return false;
+        };
+        step.apply(root).map(|v| self.redo.push(v)).is_some()
+    }
+
+    pub fn redo(&mut self, root: &mut Value) -> bool {
+        let Some(step) = self.redo.pop() else {
+            return false;
+        };
+        step.apply(root).map(|v| self.undo.push(v)).is_some()
+    }
+}
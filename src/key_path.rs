@@ -0,0 +1,294 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::{fmt::Write, iter::Peekable, str::Chars};
+
+use plist::Value;
+
+use crate::utils::pv;
+
+/// A syntax a node's key path can be copied in, for use in scripts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyPathFormat {
+    /// `:Kernel:Add:0`, as taken by `PlistBuddy -c`.
+    PlistBuddy,
+    /// `Kernel.Add.0`, with dots in keys backslash-escaped.
+    Dotted,
+    /// `/Kernel/Add/0`, per RFC 6901.
+    JsonPointer,
+    /// `["Kernel"]["Add"][0]`, with keys as Rust string literals.
+    Rust,
+    /// `['Kernel']['Add'][0]`, with keys as single-quoted Python string literals.
+    Python,
+}
+
+impl KeyPathFormat {
+    pub const ALL: [Self; 5] = [
+        Self::PlistBuddy,
+        Self::Dotted,
+        Self::JsonPointer,
+        Self::Rust,
+        Self::Python,
+    ];
+
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::PlistBuddy => "PlistBuddy",
+            Self::Dotted => "Dotted",
+            Self::JsonPointer => "JSON Pointer",
+            Self::Rust => "Rust Index",
+            Self::Python => "Python Subscript",
+        }
+    }
+
+    /// Writes out the path to the node at `path` in `root`. Indexing expressions need to know
+    /// which keys are array indices, hence the document.
+    #[must_use]
+    pub fn format(self, path: &[String], root: &Value) -> String {
+        let mut ret = String::new();
+        for (i, k) in path.iter().enumerate() {
+            let in_array = || matches!(pv(&path[..i], root), Value::Array(_));
+            match self {
+                Self::PlistBuddy => {
+                    ret.push(':');
+                    ret.push_str(&escape_key(k, ':'));
+                }
+                Self::Dotted => {
+                    if i != 0 {
+                        ret.push('.');
+                    }
+                    ret.push_str(&escape_key(k, '.'));
+                }
+                Self::JsonPointer => {
+                    ret.push('/');
+                    ret.push_str(&escape_key(k, '/'));
+                }
+                Self::Rust | Self::Python if in_array() => write!(ret, "[{k}]").unwrap(),
+                Self::Rust => write!(ret, "[{k:?}]").unwrap(),
+                Self::Python => write!(ret, "[{}]", python_str(k)).unwrap(),
+            }
+        }
+        ret
+    }
+
+    /// Splits a key path written out by [`format`](Self::format) back into its keys. `None` for
+    /// a JSON Pointer not starting with `/`, a PlistBuddy path not starting with `:`, or
+    /// indexing expressions that don't parse.
+    #[must_use]
+    pub fn split(self, text: &str) -> Option<Vec<String>> {
+        if text.is_empty() {
+            return Some(Vec::new());
+        }
+        match self {
+            Self::PlistBuddy => Some(split_keys(text.strip_prefix(':')?, ':')),
+            Self::Dotted => Some(split_keys(text, '.')),
+            Self::JsonPointer => Some(split_keys(text.strip_prefix('/')?, '/')),
+            Self::Rust | Self::Python => split_subscripts(text),
+        }
+    }
+}
+
+/// Splits `text` on `sep`, undoing the escapes [`escape_key`] adds: `~0` and `~1` between
+/// slashes, as in a JSON Pointer, and backslashes otherwise.
+#[must_use]
+pub fn split_keys(text: &str, sep: char) -> Vec<String> {
+    if sep == '/' {
+        return text
+            .split('/')
+            .map(|v| v.replace("~1", "/").replace("~0", "~"))
+            .collect();
+    }
+    let mut ret = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => ret.last_mut().unwrap().extend(chars.next()),
+            c if c == sep => ret.push(String::new()),
+            c => ret.last_mut().unwrap().push(c),
+        }
+    }
+    ret
+}
+
+/// Escapes `sep` in a key so [`split_keys`] reads it back whole.
+#[must_use]
+pub fn escape_key(k: &str, sep: char) -> String {
+    if sep == '/' {
+        return k.replace('~', "~0").replace('/', "~1");
+    }
+    let mut ret = String::with_capacity(k.len());
+    for c in k.chars() {
+        if c == sep || c == '\\' {
+            ret.push('\\');
+        }
+        ret.push(c);
+    }
+    ret
+}
+
+/// Reads back `["a"][0]`-style subscripts, with keys quoted either way and Rust's or Python's
+/// escapes.
+fn split_subscripts(text: &str) -> Option<Vec<String>> {
+    let mut ret = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '[' {
+            return None;
+        }
+        let mut key = String::new();
+        match chars.next()? {
+            quote @ ('"' | '\'') => loop {
+                match chars.next()? {
+                    '\\' => key.push(unescape(&mut chars)?),
+                    c if c == quote => break,
+                    c => key.push(c),
+                }
+            },
+            c => {
+                key.push(c);
+                while let Some(c) = chars.next_if(|v| *v != ']') {
+                    key.push(c);
+                }
+            }
+        }
+        if chars.next()? != ']' {
+            return None;
+        }
+        ret.push(key);
+    }
+    Some(ret)
+}
+
+/// The character a backslash escape stands for, with the backslash already read.
+fn unescape(chars: &mut Peekable<Chars<'_>>) -> Option<char> {
+    Some(match chars.next()? {
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        '0' => '\0',
+        'u' => {
+            let hex = if chars.next_if_eq(&'{').is_some() {
+                chars.by_ref().take_while(|v| *v != '}').collect::<String>()
+            } else {
+                chars.by_ref().take(4).collect()
+            };
+            char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+        }
+        c => c,
+    })
+}
+
+/// A single-quoted Python string literal.
+fn python_str(k: &str) -> String {
+    let mut ret = String::from('\'');
+    for c in k.chars() {
+        match c {
+            '\'' => ret.push_str("\\'"),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            c if c.is_control() => write!(ret, "\\u{:04x}", u32::from(c)).unwrap(),
+            c => ret.push(c),
+        }
+    }
+    ret.push('\'');
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use plist::{Dictionary, Value};
+
+    use super::KeyPathFormat;
+
+    fn dict<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Dictionary(Dictionary::from_iter(entries))
+    }
+
+    fn path(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|&v| v.to_owned()).collect()
+    }
+
+    const ODD: &str = "a:b/c~d\\e.f'g\"h\n\u{1}é";
+
+    fn root() -> Value {
+        dict([
+            (
+                "Kernel",
+                dict([(
+                    "Add",
+                    Value::Array(vec![dict([("BundlePath", Value::from("Lilu.kext"))])]),
+                )]),
+            ),
+            (
+                ODD,
+                dict([("0", Value::Array(vec![dict([(ODD, 1.into())])]))]),
+            ),
+        ])
+    }
+
+    fn format(format: KeyPathFormat, keys: &[&str]) -> String {
+        format.format(&path(keys), &root())
+    }
+
+    #[test]
+    fn formats() {
+        let keys = ["Kernel", "Add", "0", "BundlePath"];
+        let expected = [
+            ":Kernel:Add:0:BundlePath",
+            "Kernel.Add.0.BundlePath",
+            "/Kernel/Add/0/BundlePath",
+            r#"["Kernel"]["Add"][0]["BundlePath"]"#,
+            "['Kernel']['Add'][0]['BundlePath']",
+        ];
+        for (format, expected) in KeyPathFormat::ALL.into_iter().zip(expected) {
+            assert_eq!(self::format(format, &keys), expected, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn escaping() {
+        let keys = [ODD, "0"];
+        assert_eq!(
+            format(KeyPathFormat::PlistBuddy, &keys),
+            ":a\\:b/c~d\\\\e.f'g\"h\n\u{1}é:0"
+        );
+        assert_eq!(
+            format(KeyPathFormat::Dotted, &keys),
+            "a:b/c~d\\\\e\\.f'g\"h\n\u{1}é.0"
+        );
+        assert_eq!(
+            format(KeyPathFormat::JsonPointer, &keys),
+            "/a:b~1c~0d\\e.f'g\"h\n\u{1}é/0"
+        );
+        assert_eq!(
+            format(KeyPathFormat::Rust, &keys),
+            r#"["a:b/c~d\\e.f'g\"h\n\u{1}é"]["0"]"#
+        );
+        assert_eq!(
+            format(KeyPathFormat::Python, &keys),
+            r#"['a:b/c~d\\e.f\'g"h\n\u0001é']['0']"#
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let paths = [
+            path(&[]),
+            path(&["Kernel", "Add", "0", "BundlePath"]),
+            path(&[ODD, "0", "0", ODD]),
+        ];
+        for format in KeyPathFormat::ALL {
+            for path in &paths {
+                let text = format.format(path, &root());
+                assert_eq!(format.split(&text).as_ref(), Some(path), "{text}");
+            }
+        }
+        assert_eq!(KeyPathFormat::PlistBuddy.split("Kernel"), None);
+        assert_eq!(KeyPathFormat::JsonPointer.split("Kernel"), None);
+        assert_eq!(KeyPathFormat::Rust.split(r#"["Kernel""#), None);
+        assert_eq!(KeyPathFormat::Python.split("['Kernel']x"), None);
+    }
+}
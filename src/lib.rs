@@ -0,0 +1,33 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+#![deny(warnings, clippy::nursery, unused_extern_crates)]
+#![cfg_attr(target_os = "macos", feature(sync_unsafe_cell))]
+
+mod app;
+mod changes;
+mod clipboard;
+mod compare;
+mod convert;
+mod data_file;
+mod diff;
+mod document;
+mod filter;
+mod find;
+mod format;
+mod goto;
+mod history;
+mod key_path;
+mod merge;
+mod navigation;
+mod patch;
+mod selection;
+mod style;
+mod utils;
+mod widgets;
+
+pub use app::PlistOxide;
+pub use merge::{Merge, MergeArgs, USAGE};
+// For the benchmarks.
+pub use document::{Command, Document};
+pub use widgets::{entry::expand_to_depth, tree::Tree};
@@ -3,20 +3,15 @@
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 #![deny(warnings, clippy::nursery, unused_extern_crates)]
-#![cfg_attr(target_os = "macos", feature(sync_unsafe_cell))]
 
-use std::path::PathBuf;
+use std::{ffi::OsString, path::PathBuf};
 
-use app::PlistOxide;
 use eframe::NativeOptions;
 use egui::ViewportBuilder;
+use plist::Value;
+use plistoxide::{Merge, MergeArgs, PlistOxide};
 
-mod app;
-mod style;
-mod utils;
-mod widgets;
-
-fn run_native(renderer: eframe::Renderer) -> eframe::Result {
+fn run_native(renderer: eframe::Renderer, merge: Option<(Value, Merge)>) -> eframe::Result {
     eframe::run_native(
         "PlistOxide",
         NativeOptions {
@@ -32,19 +27,53 @@ fn run_native(renderer: eframe::Renderer) -> eframe::Result {
             ..Default::default()
         },
         Box::new(|cc| {
-            Ok(Box::new(PlistOxide::new(
-                cc,
-                std::env::args().nth(1).map(PathBuf::from),
-            )))
+            Ok(Box::new(match merge {
+                Some((root, merge)) => PlistOxide::merging(cc, root, merge),
+                None => PlistOxide::new(cc, std::env::args().nth(1).map(PathBuf::from)),
+            }))
         }),
     )
 }
 
+/// Handles `plistoxide merge BASE LOCAL REMOTE -o MERGED`, writing the result straight away if
+/// nothing conflicts. Exits on bad arguments or files.
+fn merge(args: impl Iterator<Item = OsString>) -> Option<(Value, Merge)> {
+    let (root, merge) = match MergeArgs::parse(args).and_then(|v| Merge::from_args(&v)) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{e}\n{}", plistoxide::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if !merge.conflicts().is_empty() {
+        return Some((root, merge));
+    }
+    if let Err(e) = merge.save(&root) {
+        eprintln!("Failed to write the merged plist: {e}");
+        std::process::exit(2);
+    }
+    None
+}
+
 fn main() {
-    if let Err(e) = run_native(eframe::Renderer::Wgpu) {
+    let mut args = std::env::args_os().skip(1).peekable();
+    let merging = args.peek().is_some_and(|v| v == "merge");
+    let merge = if merging {
+        match merge(args.skip(1)) {
+            Some(v) => Some(v),
+            None => return,
+        }
+    } else {
+        None
+    };
+    if let Err(e) = run_native(eframe::Renderer::Wgpu, merge.clone()) {
         eprintln!("Failed to run with wgpu renderer, trying glow. ({e})");
-        if let Err(e) = run_native(eframe::Renderer::Glow) {
+        if let Err(e) = run_native(eframe::Renderer::Glow, merge.clone()) {
             eprintln!("Failed to run with wgpu and glow renderer: ({e})");
         }
     }
+    // Tells git the conflicts weren't resolved unless the merged file was written.
+    if merging && !merge.is_some_and(|(_, v)| v.saved()) {
+        std::process::exit(1);
+    }
 }
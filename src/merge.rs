@@ -0,0 +1,568 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use egui::{RichText, Ui};
+use plist::{Dictionary, Value};
+
+use crate::{
+    compare::summary,
+    document::{Command, Document},
+    format::Format,
+    utils::try_pv,
+};
+
+pub const USAGE: &str = "Usage: plistoxide merge BASE LOCAL REMOTE -o MERGED";
+
+/// The files named by `plistoxide merge BASE LOCAL REMOTE -o MERGED`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MergeArgs {
+    pub base: PathBuf,
+    pub local: PathBuf,
+    pub remote: PathBuf,
+    pub output: PathBuf,
+}
+
+impl MergeArgs {
+    /// Parses the arguments following `merge`.
+    pub fn parse(args: impl IntoIterator<Item = OsString>) -> Result<Self, String> {
+        let mut files = Vec::new();
+        let mut output = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "-o" || arg == "--output" {
+                output = Some(args.next().ok_or("Missing path after -o")?);
+            } else {
+                files.push(PathBuf::from(arg));
+            }
+        }
+        let output = output.ok_or("Missing -o MERGED")?.into();
+        let [base, local, remote] = <[PathBuf; 3]>::try_from(files)
+            .map_err(|v| format!("Expected BASE, LOCAL and REMOTE, got {} files", v.len()))?;
+        Ok(Self {
+            base,
+            local,
+            remote,
+            output,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Version {
+    Base,
+    Local,
+    Remote,
+}
+
+impl Version {
+    pub const ALL: [Self; 3] = [Self::Base, Self::Local, Self::Remote];
+
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Base => "Base",
+            Self::Local => "Local",
+            Self::Remote => "Remote",
+        }
+    }
+}
+
+/// A node both sides changed in different ways.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Conflict {
+    pub path: Vec<String>,
+    /// The node in each [`Version`], `None` where it's missing.
+    pub versions: [Option<Value>; 3],
+    /// The version picked, if any. Until then the merge holds the local one.
+    pub resolved: Option<Version>,
+}
+
+impl Conflict {
+    /// The edit that puts `version` in place of the merged node.
+    #[must_use]
+    pub fn resolve(&self, version: Version, merged: &Value) -> Option<Command> {
+        let path = self.path.clone();
+        match (
+            try_pv(&self.path, merged).is_some(),
+            self.versions[version as usize].clone(),
+        ) {
+            (true, Some(value)) => Some(Command::Set { path, value }),
+            (true, None) => Some(Command::Remove { path }),
+            (false, Some(value)) => Some(Command::Insert { path, value }),
+            (false, None) => None,
+        }
+    }
+}
+
+/// Merges the entries of three dictionaries. Entries keep the local order, with those only the
+/// remote added placed after the entry they follow there.
+fn merge_dicts(
+    base: &Dictionary,
+    local: &Dictionary,
+    remote: &Dictionary,
+    path: &mut Vec<String>,
+    conflicts: &mut Vec<Conflict>,
+) -> Dictionary {
+    let mut keys = local.keys().collect::<Vec<_>>();
+    let remote_keys = remote.keys().collect::<Vec<_>>();
+    for (i, k) in remote_keys.iter().enumerate() {
+        if local.contains_key(k) {
+            continue;
+        }
+        let at = remote_keys[..i]
+            .iter()
+            .rev()
+            .find_map(|v| keys.iter().position(|k| k == v))
+            .map_or(0, |v| v + 1);
+        keys.insert(at, k);
+    }
+    let mut ret = Dictionary::new();
+    for k in keys {
+        path.push(k.clone());
+        if let Some(v) = merge_node(base.get(k), local.get(k), remote.get(k), path, conflicts) {
+            ret.insert(k.clone(), v);
+        }
+        path.pop();
+    }
+    ret
+}
+
+/// Merges one node, `None` meaning it's removed. Dictionaries are merged key by key, and arrays
+/// item by item as long as none of them changed length. Anything else changed on both sides is a
+/// conflict, left as the local version.
+fn merge_node(
+    base: Option<&Value>,
+    local: Option<&Value>,
+    remote: Option<&Value>,
+    path: &mut Vec<String>,
+    conflicts: &mut Vec<Conflict>,
+) -> Option<Value> {
+    if local == remote || remote == base {
+        return local.cloned();
+    }
+    if local == base {
+        return remote.cloned();
+    }
+    match (base, local, remote) {
+        (
+            None | Some(Value::Dictionary(_)),
+            Some(Value::Dictionary(l)),
+            Some(Value::Dictionary(r)),
+        ) => {
+            let empty = Dictionary::new();
+            let b = base.and_then(Value::as_dictionary).unwrap_or(&empty);
+            return Some(Value::Dictionary(merge_dicts(b, l, r, path, conflicts)));
+        }
+        (Some(Value::Array(b)), Some(Value::Array(l)), Some(Value::Array(r)))
+            if b.len() == l.len() && l.len() == r.len() =>
+        {
+            let items = (0..b.len())
+                .filter_map(|i| {
+                    path.push(i.to_string());
+                    let ret = merge_node(Some(&b[i]), Some(&l[i]), Some(&r[i]), path, conflicts);
+                    path.pop();
+                    ret
+                })
+                .collect();
+            return Some(Value::Array(items));
+        }
+        _ => {}
+    }
+    conflicts.push(Conflict {
+        path: path.clone(),
+        versions: [base.cloned(), local.cloned(), remote.cloned()],
+        resolved: None,
+    });
+    local.cloned()
+}
+
+/// Merges the changes made to `base` in `local` and `remote`, returning the result and whatever
+/// couldn't be merged.
+#[must_use]
+pub fn merge(base: &Value, local: &Value, remote: &Value) -> (Value, Vec<Conflict>) {
+    let mut conflicts = Vec::new();
+    let merged = merge_node(
+        Some(base),
+        Some(local),
+        Some(remote),
+        &mut Vec::new(),
+        &mut conflicts,
+    )
+    .unwrap_or_else(|| local.clone());
+    (merged, conflicts)
+}
+
+/// What the merge panel asks of the rest of the app after a frame.
+#[derive(Default)]
+pub struct MergeResponse {
+    /// A conflict to select and scroll to.
+    pub reveal: Option<Vec<String>>,
+    /// Whether the merged file was written and the app should quit.
+    pub quit: bool,
+}
+
+/// A three-way merge whose result is the open document, with the conflicts left to resolve.
+#[derive(Clone, Debug)]
+pub struct Merge {
+    conflicts: Vec<Conflict>,
+    /// Where the result goes, written the way the local file was, when merging for git.
+    output: Option<(PathBuf, Format)>,
+    /// Whether the output file has been written, which is what the exit status reports. Clones
+    /// share it, so it can still be read once the app holding the merge has closed.
+    saved: Arc<AtomicBool>,
+    error: Option<String>,
+}
+
+impl Merge {
+    /// Merges three files, returning the result along with the merge.
+    pub fn load(
+        base: &Path,
+        local: &Path,
+        remote: &Path,
+        output: Option<PathBuf>,
+    ) -> Result<(Value, Self), String> {
+        let (base, _) = Format::read_file(base)?;
+        let (local, format) = Format::read_file(local)?;
+        let (remote, _) = Format::read_file(remote)?;
+        let (merged, conflicts) = merge(&base, &local, &remote);
+        let output = output.map(|v| (v, format));
+        Ok((
+            merged,
+            Self {
+                conflicts,
+                output,
+                saved: Arc::default(),
+                error: None,
+            },
+        ))
+    }
+
+    /// Merges the files named on the command line.
+    pub fn from_args(args: &MergeArgs) -> Result<(Value, Self), String> {
+        Self::load(
+            &args.base,
+            &args.local,
+            &args.remote,
+            Some(args.output.clone()),
+        )
+    }
+
+    #[must_use]
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+
+    #[must_use]
+    pub fn output(&self) -> Option<&Path> {
+        self.output.as_ref().map(|(v, _)| v.as_path())
+    }
+
+    /// Writes `root` to the output file.
+    pub fn save(&self, root: &Value) -> Result<(), String> {
+        let Some((path, format)) = &self.output else {
+            return Ok(());
+        };
+        format.write_file(path, root)?;
+        self.saved.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Whether the output file has been written, by this merge or a clone of it.
+    #[must_use]
+    pub fn saved(&self) -> bool {
+        self.saved.load(Ordering::Relaxed)
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, document: &mut Document) -> MergeResponse {
+        let mut ret = MergeResponse::default();
+        let resolved = self
+            .conflicts
+            .iter()
+            .filter(|v| v.resolved.is_some())
+            .count();
+        ui.heading("Merge");
+        ui.label(format!(
+            "{resolved} of {} conflicts resolved",
+            self.conflicts.len()
+        ));
+        if self.output.is_some() {
+            let done = resolved == self.conflicts.len();
+            if ui
+                .add_enabled(done, egui::Button::new("Save and Quit"))
+                .on_disabled_hover_text("Resolve every conflict first")
+                .clicked()
+            {
+                self.error = self.save(document.root()).err();
+                ret.quit = self.error.is_none();
+            }
+            if let Some(error) = &self.error {
+                ui.label(RichText::new(error).color(ui.visuals().error_fg_color));
+            }
+        }
+        ui.separator();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for conflict in &mut self.conflicts {
+                let title = if conflict.path.is_empty() {
+                    "Root".to_owned()
+                } else {
+                    conflict.path.join(" › ")
+                };
+                if try_pv(&conflict.path, document.root()).is_some() {
+                    if ui.link(RichText::new(title).strong()).clicked() {
+                        ret.reveal = Some(conflict.path.clone());
+                    }
+                } else {
+                    ui.strong(title);
+                }
+                for version in Version::ALL {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .selectable_label(conflict.resolved == Some(version), version.label())
+                            .on_hover_text(format!("Use the {} version", version.label()))
+                            .clicked()
+                        {
+                            if let Some(command) = conflict.resolve(version, document.root()) {
+                                document.apply(command);
+                                document.commit();
+                            }
+                            conflict.resolved = Some(version);
+                        }
+                        match &conflict.versions[version as usize] {
+                            Some(v) => ui.add(egui::Label::new(summary(v)).truncate()),
+                            None => ui.weak("Missing"),
+                        };
+                    });
+                }
+                ui.separator();
+            }
+        });
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsString, path::PathBuf};
+
+    use plist::{Dictionary, Value};
+
+    use super::{Merge, MergeArgs, Version, merge};
+
+    fn dict<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Dictionary(Dictionary::from_iter(entries))
+    }
+
+    fn array<const N: usize>(items: [i64; N]) -> Value {
+        Value::Array(items.into_iter().map(Value::from).collect())
+    }
+
+    fn path(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|&v| v.to_owned()).collect()
+    }
+
+    fn keys(value: &Value) -> Vec<String> {
+        value.as_dictionary().unwrap().keys().cloned().collect()
+    }
+
+    fn conflict_paths(base: &Value, local: &Value, remote: &Value) -> Vec<Vec<String>> {
+        merge(base, local, remote)
+            .1
+            .into_iter()
+            .map(|v| v.path)
+            .collect()
+    }
+
+    #[test]
+    fn one_side_changed() {
+        let base = dict([("a", Value::from(1)), ("b", Value::from(2))]);
+        let changed = dict([("a", Value::from(3)), ("c", Value::from(4))]);
+        assert_eq!(merge(&base, &changed, &base), (changed.clone(), Vec::new()));
+        assert_eq!(merge(&base, &base, &changed), (changed.clone(), Vec::new()));
+        assert_eq!(merge(&base, &changed, &changed), (changed, Vec::new()));
+    }
+
+    #[test]
+    fn both_deleted() {
+        let base = dict([("a", Value::from(1)), ("b", Value::from(2))]);
+        let local = dict([("b", Value::from(2))]);
+        let remote = dict([("b", Value::from(3))]);
+        let (merged, conflicts) = merge(&base, &local, &remote);
+        assert!(conflicts.is_empty(), "{conflicts:?}");
+        assert_eq!(merged, dict([("b", Value::from(3))]));
+    }
+
+    #[test]
+    fn deleted_and_modified() {
+        let base = dict([("a", Value::from(1))]);
+        let deleted = dict([]);
+        let modified = dict([("a", Value::from(2))]);
+
+        let (merged, conflicts) = merge(&base, &deleted, &modified);
+        assert_eq!(merged, deleted);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].path, path(&["a"]));
+        assert_eq!(
+            conflicts[0].versions,
+            [Some(Value::from(1)), None, Some(Value::from(2))]
+        );
+        // Resolving puts the chosen version in, or takes it out.
+        let mut value = merged.clone();
+        let remote = conflicts[0].resolve(Version::Remote, &value).unwrap();
+        remote.apply(&mut value).unwrap();
+        assert_eq!(value, modified);
+        assert_eq!(conflicts[0].resolve(Version::Local, &merged), None);
+        let local = conflicts[0].resolve(Version::Local, &value).unwrap();
+        local.apply(&mut value).unwrap();
+        assert_eq!(value, deleted);
+
+        assert_eq!(
+            conflict_paths(&base, &modified, &deleted),
+            [path(&["a"])],
+            "the other way round"
+        );
+    }
+
+    #[test]
+    fn both_added() {
+        let base = dict([]);
+        let local = dict([("n", dict([("x", Value::from(1)), ("y", Value::from(2))]))]);
+        let remote = dict([("n", dict([("z", Value::from(3)), ("y", Value::from(2))]))]);
+        let (merged, conflicts) = merge(&base, &local, &remote);
+        assert!(conflicts.is_empty(), "{conflicts:?}");
+        let n = &merged.as_dictionary().unwrap()["n"];
+        assert_eq!(keys(n), path(&["z", "x", "y"]));
+
+        let remote = dict([("n", dict([("x", Value::from(3))]))]);
+        let (merged, conflicts) = merge(&base, &local, &remote);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].path, path(&["n", "x"]));
+        assert_eq!(conflicts[0].versions[0], None);
+        assert_eq!(merged, local);
+
+        // Added as different types.
+        let remote = dict([("n", array([1]))]);
+        assert_eq!(conflict_paths(&base, &local, &remote), [path(&["n"])]);
+    }
+
+    #[test]
+    fn arrays() {
+        let base = dict([("a", array([1, 2, 3]))]);
+        let local = dict([("a", array([9, 2, 3]))]);
+        let remote = dict([("a", array([1, 2, 8]))]);
+        let (merged, conflicts) = merge(&base, &local, &remote);
+        assert!(conflicts.is_empty(), "{conflicts:?}");
+        assert_eq!(merged, dict([("a", array([9, 2, 8]))]));
+
+        let remote = dict([("a", array([7, 2, 3]))]);
+        assert_eq!(conflict_paths(&base, &local, &remote), [path(&["a", "0"])]);
+
+        // Once either side changes the length, items can't be lined up.
+        let remote = dict([("a", array([1, 2, 3, 4]))]);
+        let (merged, conflicts) = merge(&base, &local, &remote);
+        assert_eq!(merged, local);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].path, path(&["a"]));
+    }
+
+    #[test]
+    fn remote_order() {
+        let base = dict([("a", Value::from(1)), ("b", Value::from(2))]);
+        let local = dict([
+            ("b", Value::from(2)),
+            ("l", Value::from(0)),
+            ("a", Value::from(1)),
+        ]);
+        let remote = dict([
+            ("first", Value::from(0)),
+            ("a", Value::from(1)),
+            ("r1", Value::from(0)),
+            ("r2", Value::from(0)),
+            ("b", Value::from(2)),
+            ("last", Value::from(0)),
+        ]);
+        let (merged, conflicts) = merge(&base, &local, &remote);
+        assert!(conflicts.is_empty(), "{conflicts:?}");
+        assert_eq!(
+            keys(&merged),
+            path(&["first", "b", "last", "l", "a", "r1", "r2"])
+        );
+    }
+
+    #[test]
+    fn args() {
+        let parse = |args: &[&str]| MergeArgs::parse(args.iter().map(OsString::from));
+        let args = parse(&["B", "-o", "M", "L", "R"]).unwrap();
+        assert_eq!(
+            [args.base, args.local, args.remote, args.output],
+            ["B", "L", "R", "M"].map(PathBuf::from)
+        );
+        assert_eq!(
+            parse(&["B", "L", "R", "--output", "M"]).unwrap().output,
+            PathBuf::from("M")
+        );
+        assert_eq!(parse(&["B", "L", "R"]), Err("Missing -o MERGED".to_owned()));
+        assert_eq!(
+            parse(&["B", "L", "R", "-o"]),
+            Err("Missing path after -o".to_owned())
+        );
+        assert_eq!(
+            parse(&["B", "L", "-o", "M"]),
+            Err("Expected BASE, LOCAL and REMOTE, got 2 files".to_owned())
+        );
+        assert_eq!(
+            parse(&["B", "L", "R", "X", "-o", "M"]),
+            Err("Expected BASE, LOCAL and REMOTE, got 4 files".to_owned())
+        );
+    }
+
+    #[test]
+    fn save() {
+        let dir = std::env::temp_dir().join(format!("plistoxide-merge-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let base = dict([("a", Value::from(1))]);
+        base.to_file_xml(dir.join("base.plist")).unwrap();
+        dict([("a", Value::from(2))])
+            .to_file_binary(dir.join("local.plist"))
+            .unwrap();
+        dict([("a", Value::from(3))])
+            .to_file_xml(dir.join("remote.plist"))
+            .unwrap();
+        let (root, merge) = Merge::load(
+            &dir.join("base.plist"),
+            &dir.join("local.plist"),
+            &dir.join("remote.plist"),
+            Some(dir.join("merged.plist")),
+        )
+        .unwrap();
+        assert_eq!(merge.conflicts().len(), 1);
+
+        let app = merge.clone();
+        assert!(!merge.saved());
+        app.save(&root).unwrap();
+        assert!(merge.saved(), "clones share whether the file was written");
+        // Written the way the local file was.
+        let written = std::fs::read(dir.join("merged.plist")).unwrap();
+        assert!(written.starts_with(b"bplist00"));
+
+        let (_, unsaved) = Merge::load(
+            &dir.join("base.plist"),
+            &dir.join("local.plist"),
+            &dir.join("remote.plist"),
+            None,
+        )
+        .unwrap();
+        unsaved.save(&root).unwrap();
+        assert!(!unsaved.saved());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
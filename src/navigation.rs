@@ -0,0 +1,157 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use egui::{Context, FocusDirection, Key, KeyboardShortcut, Modifiers};
+use plist::Value;
+
+use crate::{
+    document::Command,
+    selection::{Column, Selection},
+    utils::{ValueType, add_child, try_pv},
+    widgets::entry,
+};
+
+pub const NEW_CHILD_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::N);
+
+/// What keyboard navigation asks of the rest of the app after a frame.
+#[derive(Default)]
+pub struct NavigationResponse {
+    /// A row the cursor moved to, to keep in view.
+    pub scroll_to: Option<Vec<String>>,
+    pub command: Option<Command>,
+}
+
+fn is_container(path: &[String], root: &Value) -> bool {
+    try_pv(path, root).is_some_and(|v| ValueType::from_val(&[], v).is_expandable())
+}
+
+fn has_children(path: &[String], root: &Value) -> bool {
+    match try_pv(path, root) {
+        Some(Value::Dictionary(v)) => !v.is_empty(),
+        Some(Value::Array(v)) => !v.is_empty(),
+        _ => false,
+    }
+}
+
+/// Collapses the cursor's row, or moves to its parent if it already is.
+fn left(ctx: &Context, selection: &mut Selection, root: &Value) -> Option<Vec<String>> {
+    let path = selection.cursor()?.to_vec();
+    if is_container(&path, root) && entry::is_expanded(ctx, &path) {
+        entry::set_expanded(ctx, &path, false);
+        return None;
+    }
+    let (_, parent) = path.split_last()?;
+    selection.set(parent.to_vec());
+    Some(parent.to_vec())
+}
+
+/// Expands the cursor's row, or moves to its first child if it already is.
+fn right(ctx: &Context, selection: &mut Selection, root: &Value) -> Option<Vec<String>> {
+    let path = selection.cursor()?.to_vec();
+    if !is_container(&path, root) {
+        return None;
+    }
+    if !entry::is_expanded(ctx, &path) {
+        entry::set_expanded(ctx, &path, true);
+        None
+    } else if has_children(&path, root) {
+        selection.move_cursor(1, false)
+    } else {
+        None
+    }
+}
+
+/// Removes the selected rows, leaving the cursor on the row above the first. Nothing removed
+/// comes before it, so its path still holds afterwards.
+fn delete(selection: &mut Selection, root: &Value) -> Option<Command> {
+    let before = selection
+        .cursor()
+        .and_then(|v| selection.row_before(v))
+        .map(<[String]>::to_vec);
+    let ret = selection.remove(root)?;
+    if let Some(path) = before {
+        selection.set(path);
+    }
+    Some(ret)
+}
+
+/// Adds a child to the cursor's row, or a sibling if it can't hold children, and starts editing
+/// it.
+fn new_child(
+    ctx: &Context,
+    selection: &mut Selection,
+    root: &Value,
+) -> Option<(Vec<String>, Command)> {
+    let mut parent = selection.cursor().map_or_else(Vec::new, <[String]>::to_vec);
+    if !is_container(&parent, root) {
+        parent.pop();
+    }
+    let (command, key) = add_child(&parent, root)?;
+    let column = if matches!(try_pv(&parent, root), Some(Value::Dictionary(_))) {
+        Column::Key
+    } else {
+        Column::Value
+    };
+    let path = parent
+        .iter()
+        .cloned()
+        .chain(std::iter::once(key))
+        .collect::<Vec<_>>();
+    entry::reveal(ctx, &path);
+    selection.set(path.clone());
+    selection.request_edit(column);
+    Some((path, command))
+}
+
+/// Drives the tree's cursor from the keyboard while no text field or modal has focus.
+///
+/// Up and Down move the cursor, extending the selection with Shift, and Home and End jump to
+/// the first and last rows. Left and Right collapse and expand, Tab moves between the key, type
+/// and value cells, and Enter edits the cell.
+pub fn handle(ctx: &Context, selection: &mut Selection, root: &Value) -> NavigationResponse {
+    let mut ret = NavigationResponse::default();
+    if ctx.memory(|v| v.focused().is_some() || v.top_modal_layer().is_some()) {
+        return ret;
+    }
+    let key = |modifiers, key| ctx.input_mut(|v| v.consume_key(modifiers, key));
+    let moves = [
+        (Key::ArrowUp, -1),
+        (Key::ArrowDown, 1),
+        (Key::Home, isize::MIN),
+        (Key::End, isize::MAX),
+    ];
+    let mut handled = true;
+    if let Some((offset, extend)) = moves.into_iter().find_map(|(k, offset)| {
+        if key(Modifiers::SHIFT, k) {
+            Some((offset, true))
+        } else if key(Modifiers::NONE, k) {
+            Some((offset, false))
+        } else {
+            None
+        }
+    }) {
+        ret.scroll_to = selection.move_cursor(offset, extend);
+    } else if key(Modifiers::NONE, Key::ArrowLeft) {
+        ret.scroll_to = left(ctx, selection, root);
+    } else if key(Modifiers::NONE, Key::ArrowRight) {
+        ret.scroll_to = right(ctx, selection, root);
+    } else if selection.cursor().is_some() && key(Modifiers::SHIFT, Key::Tab) {
+        selection.set_column(selection.column().next(true));
+    } else if selection.cursor().is_some() && key(Modifiers::NONE, Key::Tab) {
+        selection.set_column(selection.column().next(false));
+    } else if selection.cursor().is_some() && key(Modifiers::NONE, Key::Enter) {
+        selection.request_edit(selection.column());
+    } else if key(Modifiers::NONE, Key::Delete) || key(Modifiers::NONE, Key::Backspace) {
+        ret.command = delete(selection, root);
+    } else if ctx.input_mut(|v| v.consume_shortcut(&NEW_CHILD_SHORTCUT)) {
+        (ret.scroll_to, ret.command) = new_child(ctx, selection, root).unzip();
+    } else {
+        handled = false;
+    }
+    if handled {
+        // Keep egui from also moving focus between widgets, and close a type menu left open.
+        ctx.memory_mut(|v| v.move_focus(FocusDirection::None));
+        egui::Popup::close_all(ctx);
+    }
+    ret
+}
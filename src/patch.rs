@@ -0,0 +1,1202 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use egui::{Context, Id, RichText};
+use plist::{Date, Uid, Value};
+use serde_json::{Map, Number, Value as Json};
+
+use crate::{
+    diff::{self, Change, Row},
+    document::Command,
+    key_path::KeyPathFormat,
+    utils::{ValueType, format_real, parse_real, try_pv},
+};
+
+/// The syntaxes a patch can be written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatchFormat {
+    /// An RFC 6902 JSON Patch.
+    JsonPatch,
+    /// A shell script running `PlistBuddy -c` on the file it's given.
+    PlistBuddy,
+}
+
+impl PatchFormat {
+    pub const ALL: [Self; 2] = [Self::JsonPatch, Self::PlistBuddy];
+
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::JsonPatch => "JSON Patch",
+            Self::PlistBuddy => "PlistBuddy Script",
+        }
+    }
+
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::JsonPatch => "json",
+            Self::PlistBuddy => "sh",
+        }
+    }
+
+    /// JSON Patches are arrays, so anything else is taken for a PlistBuddy script.
+    #[must_use]
+    pub fn detect(text: &str) -> Self {
+        if text.trim_start().starts_with('[') {
+            Self::JsonPatch
+        } else {
+            Self::PlistBuddy
+        }
+    }
+}
+
+/// One step of a patch.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Op {
+    /// Adds `value` at `path`, replacing a dictionary entry already there. The array index `-`
+    /// appends.
+    Add {
+        path: Vec<String>,
+        value: Value,
+    },
+    Remove {
+        path: Vec<String>,
+    },
+    Replace {
+        path: Vec<String>,
+        value: Value,
+    },
+    Move {
+        from: Vec<String>,
+        path: Vec<String>,
+    },
+    Copy {
+        from: Vec<String>,
+        path: Vec<String>,
+    },
+    /// Checks that `path` holds `value`, applying nothing.
+    Test {
+        path: Vec<String>,
+        value: Value,
+    },
+    /// PlistBuddy's `Set`, which reads `text` as whatever type the node already holds.
+    Set {
+        path: Vec<String>,
+        text: String,
+    },
+}
+
+/// A step as read from a patch, along with how it was written there.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub op: Op,
+    pub text: String,
+}
+
+fn title(path: &[String]) -> String {
+    if path.is_empty() {
+        "Root".to_owned()
+    } else {
+        path.join(" › ")
+    }
+}
+
+fn tagged(tag: &str, value: impl Into<Json>) -> Json {
+    Json::Object(Map::from_iter([(tag.to_owned(), value.into())]))
+}
+
+/// JSON has no data, dates or non-finite numbers, so those are written as objects with a single
+/// `$data` (in base64), `$date`, `$real` or `$uid` member.
+#[must_use]
+pub fn to_json(v: &Value) -> Json {
+    match v {
+        Value::Dictionary(v) => {
+            Json::Object(v.iter().map(|(k, v)| (k.clone(), to_json(v))).collect())
+        }
+        Value::Array(v) => Json::Array(v.iter().map(to_json).collect()),
+        Value::Boolean(v) => Json::Bool(*v),
+        Value::Integer(v) => v
+            .as_signed()
+            .map(Json::from)
+            .or_else(|| v.as_unsigned().map(Json::from))
+            .unwrap_or_default(),
+        Value::Real(v) => {
+            Number::from_f64(*v).map_or_else(|| tagged("$real", format_real(*v)), Json::Number)
+        }
+        Value::String(v) => Json::String(v.clone()),
+        Value::Data(v) => tagged("$data", STANDARD.encode(v)),
+        Value::Date(v) => tagged("$date", v.to_xml_format()),
+        Value::Uid(v) => tagged("$uid", v.get()),
+        _ => Json::Null,
+    }
+}
+
+/// Reads a value written as an object with a single tagged member, if `tag` is one.
+fn from_tagged(tag: &str, v: &Json) -> Option<Result<Value, String>> {
+    let bad = || format!("{v} isn't a valid {tag}");
+    Some(match (tag, v) {
+        ("$data", Json::String(v)) => STANDARD.decode(v).map(Value::Data).map_err(|_| bad()),
+        ("$date", Json::String(v)) => Date::from_xml_format(v).map(Value::Date).map_err(|_| bad()),
+        ("$real", Json::String(v)) => parse_real(v).map(Value::Real).ok_or_else(bad),
+        ("$uid", Json::Number(v)) => v.as_u64().map(|v| Value::Uid(Uid::new(v))).ok_or_else(bad),
+        _ => return None,
+    })
+}
+
+/// The inverse of [`to_json`].
+pub fn from_json(v: &Json) -> Result<Value, String> {
+    Ok(match v {
+        Json::Null => return Err("Plists can't hold null".to_owned()),
+        Json::Bool(v) => Value::Boolean(*v),
+        Json::Number(v) => match (v.as_i64(), v.as_u64(), v.as_f64()) {
+            (Some(v), _, _) => Value::Integer(v.into()),
+            (None, Some(v), _) => Value::Integer(v.into()),
+            (None, None, Some(v)) => Value::Real(v),
+            (None, None, None) => return Err(format!("{v} is out of range")),
+        },
+        Json::String(v) => Value::String(v.clone()),
+        Json::Array(v) => Value::Array(v.iter().map(from_json).collect::<Result<_, _>>()?),
+        Json::Object(v) => {
+            if v.len() == 1
+                && let Some((tag, value)) = v.iter().next()
+                && let Some(ret) = from_tagged(tag, value)
+            {
+                return ret;
+            }
+            Value::Dictionary(
+                v.iter()
+                    .map(|(k, v)| Ok((k.clone(), from_json(v)?)))
+                    .collect::<Result<_, String>>()?,
+            )
+        }
+    })
+}
+
+/// The step that makes the row the same on the left as on the right, if it can be taken by
+/// itself. A patch doesn't order dictionaries, so entries that only moved within one are left
+/// alone.
+fn row_op(row: &Row, roots: [&Value; 2]) -> Option<Op> {
+    if row.inherited {
+        return None;
+    }
+    let right = || try_pv(&row.paths[1], roots[1]).cloned();
+    match row.present {
+        [true, false] => Some(Op::Remove {
+            path: row.paths[0].clone(),
+        }),
+        [false, true] => Some(Op::Add {
+            path: row.paths[0].clone(),
+            value: right()?,
+        }),
+        _ if matches!(row.change, Change::Same | Change::Moved) || row.is_ancestor(roots) => None,
+        _ => Some(Op::Replace {
+            path: row.paths[0].clone(),
+            value: right()?,
+        }),
+    }
+}
+
+/// The steps that turn `from` into `to`. Each is worked out against the document as the ones
+/// before it leave it, so that array indices stay right.
+pub fn ops(from: &Value, to: &Value) -> Result<Vec<Op>, String> {
+    let mut current = from.clone();
+    let mut ret = Vec::new();
+    // Each step settles at least one row, and only now and then unsettles another.
+    let limit = diff::changes(from, to).len() * 2 + 1;
+    for _ in 0..limit {
+        let rows = diff::changes(&current, to);
+        let Some(op) = rows.iter().find_map(|v| row_op(v, [&current, to])) else {
+            break;
+        };
+        apply_op(op.clone(), &mut current)?;
+        ret.push(op);
+    }
+    if current != *to {
+        return Err("Some of the changes couldn't be written as a patch".to_owned());
+    }
+    Ok(ret)
+}
+
+fn json_op(op: &Op, root: &Value) -> Result<Json, String> {
+    let pointer = |path: &[String]| Json::String(KeyPathFormat::JsonPointer.format(path, root));
+    let (name, from, path, value) = match op {
+        Op::Add { path, value } => ("add", None, path, Some(value)),
+        Op::Remove { path } => ("remove", None, path, None),
+        Op::Replace { path, value } => ("replace", None, path, Some(value)),
+        Op::Move { from, path } => ("move", Some(from), path, None),
+        Op::Copy { from, path } => ("copy", Some(from), path, None),
+        Op::Test { path, value } => ("test", None, path, Some(value)),
+        Op::Set { path, .. } => {
+            return Err(format!(
+                "Setting {} as text can't be written as a JSON Patch",
+                title(path)
+            ));
+        }
+    };
+    let mut ret = Map::new();
+    ret.insert("op".to_owned(), name.into());
+    if let Some(from) = from {
+        ret.insert("from".to_owned(), pointer(from));
+    }
+    ret.insert("path".to_owned(), pointer(path));
+    if let Some(value) = value {
+        ret.insert("value".to_owned(), to_json(value));
+    }
+    Ok(Json::Object(ret))
+}
+
+/// Double-quotes text the way PlistBuddy reads it.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', r"\\").replace('"', "\\\""))
+}
+
+/// A double-quoted PlistBuddy entry. Its backslashes are already escapes, so only quotes need
+/// escaping on top.
+fn entry(path: &[String], root: &Value) -> String {
+    let entry = KeyPathFormat::PlistBuddy.format(path, root);
+    format!("\"{}\"", entry.replace('"', "\\\""))
+}
+
+/// PlistBuddy's name for the type of `v`, along with its value as text if it isn't a container.
+fn plistbuddy_value(path: &[String], v: &Value) -> Result<(&'static str, Option<String>), String> {
+    Ok(match v {
+        Value::Dictionary(_) => ("dict", None),
+        Value::Array(_) => ("array", None),
+        Value::Boolean(v) => ("bool", Some(v.to_string())),
+        Value::Integer(v) => ("integer", Some(v.to_string())),
+        Value::Real(v) => ("real", Some(format_real(*v))),
+        Value::String(v) => ("string", Some(v.clone())),
+        Value::Date(v) => ("date", Some(v.to_xml_format())),
+        // PlistBuddy takes data as the bytes of the text it's given.
+        Value::Data(v) => match String::from_utf8(v.clone()) {
+            Ok(v) => ("data", Some(v)),
+            Err(_) => {
+                return Err(format!(
+                    "{} holds binary data, which PlistBuddy can't write",
+                    title(path)
+                ));
+            }
+        },
+        _ => {
+            return Err(format!(
+                "PlistBuddy can't write the value of {}",
+                title(path)
+            ));
+        }
+    })
+}
+
+/// `Add` commands for what's below `v`, which is already at `path`.
+fn plistbuddy_children(
+    path: &mut Vec<String>,
+    v: &Value,
+    root: &Value,
+    out: &mut Vec<String>,
+) -> Result<(), String> {
+    let children = match v {
+        Value::Dictionary(v) => v.iter().map(|(k, v)| (k.clone(), v)).collect(),
+        Value::Array(v) => v
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), v))
+            .collect(),
+        _ => Vec::new(),
+    };
+    for (k, v) in children {
+        path.push(k);
+        let ret = plistbuddy_add(path, v, root, out);
+        path.pop();
+        ret?;
+    }
+    Ok(())
+}
+
+/// `Add` commands for `v` and everything below it.
+fn plistbuddy_add(
+    path: &mut Vec<String>,
+    v: &Value,
+    root: &Value,
+    out: &mut Vec<String>,
+) -> Result<(), String> {
+    let command = match plistbuddy_value(path, v)? {
+        (ty, Some(text)) => format!("Add {} {ty} {}", entry(path, root), quote(&text)),
+        (ty, None) => format!("Add {} {ty}", entry(path, root)),
+    };
+    out.push(command);
+    plistbuddy_children(path, v, root, out)
+}
+
+/// The PlistBuddy commands for `op`, applied to `root`.
+fn plistbuddy_op(op: &Op, root: &Value, out: &mut Vec<String>) -> Result<(), String> {
+    match op {
+        Op::Add { path, value } => plistbuddy_add(&mut path.clone(), value, root, out),
+        Op::Remove { path } => {
+            out.push(format!("Delete {}", entry(path, root)));
+            Ok(())
+        }
+        Op::Replace { path, value } if path.is_empty() => match plistbuddy_value(path, value)? {
+            (ty, None) => {
+                out.push(format!("Clear {ty}"));
+                plistbuddy_children(&mut Vec::new(), value, root, out)
+            }
+            (_, Some(_)) => Err("PlistBuddy can't make the root a single value".to_owned()),
+        },
+        Op::Replace { path, value } => {
+            let same_type = try_pv(path, root)
+                .is_some_and(|v| std::mem::discriminant(v) == std::mem::discriminant(value));
+            match plistbuddy_value(path, value)? {
+                // `Set` keeps the type, so it does for single values that keep theirs.
+                (_, Some(text)) if same_type => {
+                    out.push(format!("Set {} {}", entry(path, root), quote(&text)));
+                    Ok(())
+                }
+                _ => {
+                    out.push(format!("Delete {}", entry(path, root)));
+                    plistbuddy_add(&mut path.clone(), value, root, out)
+                }
+            }
+        }
+        Op::Copy { from, path } => {
+            out.push(format!("Copy {} {}", entry(from, root), entry(path, root)));
+            Ok(())
+        }
+        Op::Set { path, text } => {
+            out.push(format!("Set {} {}", entry(path, root), quote(text)));
+            Ok(())
+        }
+        Op::Move { .. } | Op::Test { .. } => {
+            Err("Moves and tests can't be written as PlistBuddy commands".to_owned())
+        }
+    }
+}
+
+/// Single-quotes text for the shell.
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// Writes out the steps that turn `from` into `to`.
+pub fn export(from: &Value, to: &Value, format: PatchFormat) -> Result<String, String> {
+    let mut current = from.clone();
+    let mut ret = match format {
+        PatchFormat::JsonPatch => String::new(),
+        PatchFormat::PlistBuddy => String::from(
+            "#!/bin/sh\n# Usage: sh patch.sh File.plist\nset -e\nPlistBuddy=/usr/libexec/PlistBuddy\n",
+        ),
+    };
+    let mut json = Vec::new();
+    for op in ops(from, to)? {
+        match format {
+            PatchFormat::JsonPatch => json.push(json_op(&op, &current)?),
+            PatchFormat::PlistBuddy => {
+                let mut commands = Vec::new();
+                plistbuddy_op(&op, &current, &mut commands)?;
+                for command in commands {
+                    let command = shell_quote(&command);
+                    ret.push_str(&format!("\"$PlistBuddy\" -c {command} \"$1\"\n"));
+                }
+            }
+        }
+        apply_op(op, &mut current)?;
+    }
+    if format == PatchFormat::JsonPatch {
+        ret = serde_json::to_string_pretty(&json).map_err(|e| e.to_string())?;
+        ret.push('\n');
+    }
+    Ok(ret)
+}
+
+fn exists<'a>(path: &[String], root: &'a Value) -> Result<&'a Value, String> {
+    try_pv(path, root).ok_or_else(|| format!("{} doesn't exist", title(path)))
+}
+
+/// The edit that adds `value` at `path`, or replaces the dictionary entry there.
+fn add_command(path: Vec<String>, value: Value, root: &Value) -> Result<Command, String> {
+    let Some((k, parent)) = path.split_last() else {
+        return Ok(Command::Set { path, value });
+    };
+    match exists(parent, root)? {
+        Value::Dictionary(v) if v.contains_key(k) => Ok(Command::Set { path, value }),
+        Value::Dictionary(_) => Ok(Command::Insert { path, value }),
+        Value::Array(v) => {
+            let i = if k == "-" {
+                v.len()
+            } else {
+                k.parse::<usize>()
+                    .ok()
+                    .filter(|&i| i <= v.len())
+                    .ok_or_else(|| format!("{} has no index {k}", title(parent)))?
+            };
+            let mut path = parent.to_vec();
+            path.push(i.to_string());
+            Ok(Command::Insert { path, value })
+        }
+        _ => Err(format!("{} isn't a dictionary or array", title(parent))),
+    }
+}
+
+/// Reads `text` the way PlistBuddy does for a value of type `ty`.
+fn plistbuddy_parse(ty: ValueType, text: &str) -> Option<Value> {
+    match ty {
+        ValueType::Array | ValueType::Dictionary => Some(ty.default_value()),
+        ValueType::Data => Some(Value::Data(text.as_bytes().to_vec())),
+        _ => ty.parse_value(text),
+    }
+}
+
+/// Applies `op` to `root`, returning the edits that did it.
+fn apply_op(op: Op, root: &mut Value) -> Result<Vec<Command>, String> {
+    let commands = match op {
+        Op::Add { path, value } => vec![add_command(path, value, root)?],
+        Op::Remove { path } => {
+            if path.is_empty() {
+                return Err("The root can't be removed".to_owned());
+            }
+            exists(&path, root)?;
+            vec![Command::Remove { path }]
+        }
+        Op::Replace { path, value } => {
+            exists(&path, root)?;
+            vec![Command::Set { path, value }]
+        }
+        Op::Copy { from, path } => {
+            let value = exists(&from, root)?.clone();
+            vec![add_command(path, value, root)?]
+        }
+        Op::Move { from, path } => {
+            if from == path {
+                return Ok(Vec::new());
+            }
+            if path.starts_with(&from) {
+                return Err(format!("{} can't be moved into itself", title(&from)));
+            }
+            let value = exists(&from, root)?.clone();
+            let remove = Command::Remove { path: from };
+            let undo = remove.clone().apply(root);
+            // The destination is found with the source gone, as in RFC 6902.
+            return match add_command(path, value, root) {
+                Ok(add) => {
+                    add.clone().apply(root);
+                    Ok(vec![remove, add])
+                }
+                Err(e) => {
+                    if let Some(undo) = undo {
+                        undo.apply(root);
+                    }
+                    Err(e)
+                }
+            };
+        }
+        Op::Test { path, value } => {
+            if *exists(&path, root)? != value {
+                return Err(format!("{} doesn't hold the value expected", title(&path)));
+            }
+            Vec::new()
+        }
+        Op::Set { path, text } => {
+            let ty = ValueType::from_val(&[], exists(&path, root)?);
+            if ty.is_expandable() {
+                return Err(format!("{} is a {ty:?}, which can't be set", title(&path)));
+            }
+            let value = plistbuddy_parse(ty, &text)
+                .ok_or_else(|| format!("{text:?} isn't a valid {ty:?} for {}", title(&path)))?;
+            vec![Command::Set { path, value }]
+        }
+    };
+    for command in &commands {
+        command.clone().apply(root);
+    }
+    Ok(commands)
+}
+
+/// A step of a patch that didn't fit the document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    /// The step, as written in the patch.
+    pub step: String,
+    pub reason: String,
+}
+
+/// What applying a patch did.
+#[derive(Clone, Debug, Default)]
+pub struct Outcome {
+    /// The edits of every step that applied, undone as one.
+    pub command: Option<Command>,
+    pub applied: usize,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Applies each step that still fits `root`, skipping those that don't, such as ones whose
+/// paths no longer exist.
+#[must_use]
+pub fn apply(steps: Vec<Step>, root: &Value) -> Outcome {
+    let mut current = root.clone();
+    let mut commands = Vec::new();
+    let mut ret = Outcome::default();
+    for Step { op, text } in steps {
+        match apply_op(op, &mut current) {
+            Ok(v) => {
+                commands.extend(v);
+                ret.applied += 1;
+            }
+            Err(reason) => ret.conflicts.push(Conflict { step: text, reason }),
+        }
+    }
+    ret.command = Command::batch(commands);
+    ret
+}
+
+fn parse_json(text: &str) -> Result<Vec<Step>, String> {
+    let Json::Array(ops) = serde_json::from_str(text).map_err(|e| e.to_string())? else {
+        return Err("A JSON Patch is an array of operations".to_owned());
+    };
+    ops.iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let error = |e: String| format!("Operation {}: {e}", i + 1);
+            let member = |name: &str| v.get(name).ok_or_else(|| error(format!("No {name}")));
+            let pointer = |name: &str| {
+                let text = member(name)?
+                    .as_str()
+                    .ok_or_else(|| error(format!("The {name} isn't a string")))?;
+                KeyPathFormat::JsonPointer
+                    .split(text)
+                    .ok_or_else(|| error(format!("{text:?} isn't a JSON Pointer")))
+            };
+            let value = || from_json(member("value")?).map_err(error);
+            let op = match member("op")?.as_str() {
+                Some("add") => Op::Add {
+                    path: pointer("path")?,
+                    value: value()?,
+                },
+                Some("remove") => Op::Remove {
+                    path: pointer("path")?,
+                },
+                Some("replace") => Op::Replace {
+                    path: pointer("path")?,
+                    value: value()?,
+                },
+                Some("move") => Op::Move {
+                    from: pointer("from")?,
+                    path: pointer("path")?,
+                },
+                Some("copy") => Op::Copy {
+                    from: pointer("from")?,
+                    path: pointer("path")?,
+                },
+                Some("test") => Op::Test {
+                    path: pointer("path")?,
+                    value: value()?,
+                },
+                _ => return Err(error(format!("Unknown op {}", member("op")?))),
+            };
+            Ok(Step {
+                op,
+                text: v.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Splits a line the way the shell does, handling quotes and backslashes.
+fn shell_words(line: &str) -> Option<Vec<String>> {
+    let mut ret = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => word.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_default();
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => match chars.next()? {
+                            c @ ('"' | '\\' | '$' | '`') => word.push(c),
+                            c => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                        },
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_default().push(chars.next()?),
+            c if c.is_whitespace() => ret.extend(word.take()),
+            c => word.get_or_insert_default().push(c),
+        }
+    }
+    ret.extend(word);
+    Some(ret)
+}
+
+/// Splits a PlistBuddy command into its arguments, dropping quotes but keeping backslash
+/// escapes, which entries need to tell escaped colons apart.
+fn plistbuddy_words(command: &str) -> Option<Vec<String>> {
+    let mut ret = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quoted = false;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let word = word.get_or_insert_default();
+                word.push('\\');
+                word.push(chars.next()?);
+            }
+            '"' => {
+                quoted = !quoted;
+                word.get_or_insert_default();
+            }
+            c if c.is_whitespace() && !quoted => ret.extend(word.take()),
+            c => word.get_or_insert_default().push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    ret.extend(word);
+    Some(ret)
+}
+
+fn unescape(word: &str) -> String {
+    let mut ret = String::with_capacity(word.len());
+    let mut chars = word.chars();
+    while let Some(c) = chars.next() {
+        ret.extend(if c == '\\' { chars.next() } else { Some(c) });
+    }
+    ret
+}
+
+fn plistbuddy_type(name: &str) -> Option<ValueType> {
+    Some(match name.to_ascii_lowercase().as_str() {
+        "string" => ValueType::String,
+        "array" => ValueType::Array,
+        "dict" => ValueType::Dictionary,
+        "bool" => ValueType::Boolean,
+        "real" => ValueType::Real,
+        "integer" => ValueType::Integer,
+        "date" => ValueType::Date,
+        "data" => ValueType::Data,
+        _ => return None,
+    })
+}
+
+/// Reads one PlistBuddy command. Those that only print or save are skipped.
+fn plistbuddy_command(command: &str) -> Result<Option<Op>, String> {
+    let words = plistbuddy_words(command).ok_or("Unbalanced quotes")?;
+    let Some((verb, args)) = words.split_first() else {
+        return Ok(None);
+    };
+    let path = |i: usize| {
+        let word = args.get(i).ok_or("Missing entry")?;
+        KeyPathFormat::PlistBuddy
+            .split(word)
+            .ok_or_else(|| format!("{word} isn't an entry, which starts with :"))
+    };
+    let rest = |i: usize| {
+        args.get(i..)
+            .unwrap_or_default()
+            .iter()
+            .map(|v| unescape(v))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let typed = |i: usize| {
+        let name = unescape(args.get(i).ok_or("Missing type")?);
+        let ty = plistbuddy_type(&name).ok_or_else(|| format!("Unknown type {name}"))?;
+        let text = rest(i + 1);
+        plistbuddy_parse(ty, &text).ok_or_else(|| format!("{text:?} isn't a valid {name}"))
+    };
+    Ok(Some(match verb.to_ascii_lowercase().as_str() {
+        "add" => Op::Add {
+            path: path(0)?,
+            value: typed(1)?,
+        },
+        "set" => Op::Set {
+            path: path(0)?,
+            text: rest(1),
+        },
+        "delete" => Op::Remove { path: path(0)? },
+        "copy" => Op::Copy {
+            from: path(0)?,
+            path: path(1)?,
+        },
+        "clear" => Op::Replace {
+            path: Vec::new(),
+            value: match args.first() {
+                Some(name) => plistbuddy_type(&unescape(name))
+                    .ok_or_else(|| format!("Unknown type {name}"))?
+                    .default_value(),
+                None => ValueType::Dictionary.default_value(),
+            },
+        },
+        "print" | "save" | "revert" | "exit" | "quit" | "help" => return Ok(None),
+        _ => return Err(format!("{verb} isn't supported")),
+    }))
+}
+
+/// Reads a script of PlistBuddy commands: either commands alone, as PlistBuddy reads them on
+/// its input, or shell lines passing them with `-c`. Other shell lines are skipped.
+fn parse_plistbuddy(text: &str) -> Result<Vec<Step>, String> {
+    // Capitalised, as PlistBuddy writes them, which keeps them apart from `set -e` and the like.
+    const VERBS: [&str; 11] = [
+        "Add", "Set", "Delete", "Copy", "Clear", "Print", "Save", "Revert", "Exit", "Quit", "Help",
+    ];
+    let mut ret = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let error = |e: String| format!("Line {}: {e}", i + 1);
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let words = shell_words(line).ok_or_else(|| error("Unbalanced quotes".to_owned()))?;
+        let command = match words.iter().position(|v| v == "-c") {
+            Some(i) => words
+                .get(i + 1)
+                .ok_or_else(|| error("Missing command after -c".to_owned()))?
+                .clone(),
+            None if words.first().is_some_and(|v| VERBS.contains(&v.as_str())) => line.to_owned(),
+            None => continue,
+        };
+        if let Some(op) = plistbuddy_command(&command).map_err(error)? {
+            ret.push(Step { op, text: command });
+        }
+    }
+    Ok(ret)
+}
+
+/// Reads a patch in either format.
+pub fn parse(text: &str) -> Result<Vec<Step>, String> {
+    match PatchFormat::detect(text) {
+        PatchFormat::JsonPatch => parse_json(text),
+        PatchFormat::PlistBuddy => parse_plistbuddy(text),
+    }
+}
+
+/// The outcome of the last export or apply, shown until acknowledged.
+#[derive(Clone, Debug, Default)]
+struct Report {
+    heading: String,
+    message: String,
+    conflicts: Vec<Conflict>,
+}
+
+fn report_id() -> Id {
+    Id::new("PatchReport")
+}
+
+fn report(ctx: &Context, heading: &str, message: String, conflicts: Vec<Conflict>) {
+    ctx.data_mut(|d| {
+        d.insert_temp(
+            report_id(),
+            Report {
+                heading: heading.to_owned(),
+                message,
+                conflicts,
+            },
+        );
+    });
+}
+
+/// Writes the steps that turn `from` into `to` to a file picked by the user, in the format
+/// its extension names.
+pub fn save(ctx: &Context, from: &Value, to: &Value) {
+    let mut dialog = rfd::FileDialog::new()
+        .set_title("Export Patch")
+        .set_file_name("patch.json");
+    for format in PatchFormat::ALL {
+        dialog = dialog.add_filter(format.label(), &[format.extension()]);
+    }
+    let Some(file) = dialog.save_file() else {
+        return;
+    };
+    let format = if file
+        .extension()
+        .is_some_and(|v| v == PatchFormat::PlistBuddy.extension())
+    {
+        PatchFormat::PlistBuddy
+    } else {
+        PatchFormat::JsonPatch
+    };
+    match export(from, to, format).and_then(|v| std::fs::write(&file, v).map_err(|e| e.to_string()))
+    {
+        Ok(()) => report(
+            ctx,
+            "Exported patch",
+            format!("{}\n{}", file.display(), format.label()),
+            Vec::new(),
+        ),
+        Err(e) => report(ctx, "Error while exporting patch", e, Vec::new()),
+    }
+}
+
+/// Applies a patch picked by the user to `root`, reporting the steps that didn't fit.
+#[must_use]
+pub fn load(ctx: &Context, root: &Value) -> Option<Command> {
+    let file = rfd::FileDialog::new()
+        .set_title("Apply Patch")
+        .add_filter("Patches", &["json", "sh", "txt"])
+        .pick_file()?;
+    let steps = match std::fs::read_to_string(&file)
+        .map_err(|e| e.to_string())
+        .and_then(|v| parse(&v))
+    {
+        Ok(v) => v,
+        Err(e) => {
+            report(ctx, "Error while reading patch", e, Vec::new());
+            return None;
+        }
+    };
+    let total = steps.len();
+    let outcome = apply(steps, root);
+    let heading = if outcome.conflicts.is_empty() {
+        "Applied patch"
+    } else {
+        "Applied patch with conflicts"
+    };
+    report(
+        ctx,
+        heading,
+        format!(
+            "{}\n{} of {total} steps applied",
+            file.display(),
+            outcome.applied
+        ),
+        outcome.conflicts,
+    );
+    outcome.command
+}
+
+pub fn show_report(ctx: &Context) {
+    let Some(report) = ctx.data_mut(|d| d.get_temp::<Report>(report_id())) else {
+        return;
+    };
+    let mut acked = false;
+    egui::Modal::new(Id::new("PatchModal")).show(ctx, |ui| {
+        ui.heading(report.heading);
+        ui.separator();
+        ui.label(report.message);
+        if !report.conflicts.is_empty() {
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for conflict in &report.conflicts {
+                        ui.label(RichText::new(&conflict.step).monospace());
+                        ui.label(
+                            RichText::new(&conflict.reason).color(ui.visuals().error_fg_color),
+                        );
+                        ui.add_space(4.0);
+                    }
+                });
+        }
+        ui.separator();
+        egui::Sides::new().show(
+            ui,
+            |_| {},
+            |ui| {
+                acked = ui.button("Okay").clicked();
+            },
+        )
+    });
+    if acked {
+        ctx.data_mut(|d| d.remove::<Report>(report_id()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plist::{Date, Dictionary, Uid, Value};
+    use serde_json::json;
+
+    use super::{
+        Outcome, PatchFormat, apply, export, from_json, parse, plistbuddy_words, shell_words,
+        to_json, unescape,
+    };
+    use crate::key_path::KeyPathFormat;
+
+    /// A key with every character that needs escaping in one format or another.
+    const AWKWARD: &str = r#"a/b~c:d"e'f\g h"#;
+
+    fn dict<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Dictionary(Dictionary::from_iter(entries))
+    }
+
+    fn path(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|&v| v.to_owned()).collect()
+    }
+
+    fn from() -> Value {
+        dict([
+            ("a", Value::from(1)),
+            ("b", Value::from("x")),
+            (
+                "c",
+                Value::Array(vec![Value::from(1), Value::from(2), Value::from(3)]),
+            ),
+            ("d", Value::Boolean(true)),
+            ("t", Value::from(5)),
+            ("mv", dict([("k", Value::from("v"))])),
+            ("sub", dict([])),
+            (AWKWARD, Value::Real(1.5)),
+        ])
+    }
+
+    fn to() -> Value {
+        dict([
+            ("b", Value::from(r#"y "z" 'w' \ $HOME"#)),
+            ("a", Value::from(1)),
+            ("n", Value::Data(b"hello".to_vec())),
+            (
+                "c",
+                Value::Array(vec![
+                    Value::from(3),
+                    Value::from(1),
+                    Value::from(2),
+                    Value::from(4),
+                ]),
+            ),
+            ("t", Value::from("5")),
+            (
+                "sub",
+                dict([
+                    ("mv", dict([("k", Value::from("v"))])),
+                    (
+                        "when",
+                        Value::Date(Date::from_xml_format("2024-01-02T03:04:05Z").unwrap()),
+                    ),
+                    (AWKWARD, dict([(AWKWARD, Value::from(r"\:"))])),
+                ]),
+            ),
+            (AWKWARD, Value::Real(2.0)),
+        ])
+    }
+
+    fn applied(text: &str, root: &Value) -> (Value, Outcome) {
+        let outcome = apply(parse(text).unwrap(), root);
+        let mut ret = root.clone();
+        if let Some(command) = outcome.command.clone() {
+            command.apply(&mut ret);
+        }
+        (ret, outcome)
+    }
+
+    #[test]
+    fn round_trip() {
+        for format in PatchFormat::ALL {
+            let text = export(&from(), &to(), format).unwrap();
+            assert_eq!(PatchFormat::detect(&text), format);
+            let (value, outcome) = applied(&text, &from());
+            assert!(outcome.conflicts.is_empty(), "{:?}", outcome.conflicts);
+            assert_eq!(value, to(), "{format:?}:\n{text}");
+
+            // Applied again, the steps that removed something no longer fit.
+            let (_, outcome) = applied(&text, &to());
+            assert!(!outcome.conflicts.is_empty(), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn round_trip_root_type() {
+        let from = dict([("a", Value::from(1))]);
+        let to = Value::Array(vec![Value::from("x")]);
+        for format in PatchFormat::ALL {
+            let text = export(&from, &to, format).unwrap();
+            let (value, outcome) = applied(&text, &from);
+            assert!(outcome.conflicts.is_empty(), "{:?}", outcome.conflicts);
+            assert_eq!(value, to, "{format:?}:\n{text}");
+        }
+    }
+
+    #[test]
+    fn missing_paths() {
+        let mut other = from();
+        let v = other.as_dictionary_mut().unwrap();
+        v.remove("mv");
+        v.remove("c");
+        for format in PatchFormat::ALL {
+            let text = export(&from(), &to(), format).unwrap();
+            let (value, outcome) = applied(&text, &other);
+            assert!(!outcome.conflicts.is_empty(), "{format:?}");
+            assert!(
+                outcome
+                    .conflicts
+                    .iter()
+                    .all(|v| v.reason.contains("doesn't exist")),
+                "{:?}",
+                outcome.conflicts
+            );
+            // The steps that still fit are applied.
+            let value = value.as_dictionary().unwrap();
+            assert_eq!(value.get("n"), Some(&Value::Data(b"hello".to_vec())));
+            assert_eq!(value.get("t"), Some(&Value::from("5")));
+        }
+    }
+
+    #[test]
+    fn json_patch() {
+        let text = r#"[
+            {"op": "test", "path": "/a", "value": 1},
+            {"op": "move", "from": "/a", "path": "/z"},
+            {"op": "copy", "from": "/z", "path": "/c/-"},
+            {"op": "remove", "path": "/nope"},
+            {"op": "add", "path": "/c/99", "value": 1},
+            {"op": "test", "path": "/b", "value": "not x"}
+        ]"#;
+        let (value, outcome) = applied(text, &from());
+        assert_eq!(outcome.applied, 3);
+        let reasons = outcome
+            .conflicts
+            .iter()
+            .map(|v| v.reason.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reasons,
+            [
+                "nope doesn't exist",
+                "c has no index 99",
+                "b doesn't hold the value expected",
+            ]
+        );
+        let value = value.as_dictionary().unwrap();
+        assert_eq!(value.get("a"), None);
+        assert_eq!(value.get("z"), Some(&Value::from(1)));
+        assert_eq!(value["c"].as_array().unwrap().len(), 4);
+
+        assert!(parse(r#"[{"op": "frob", "path": ""}]"#).is_err());
+        assert!(parse(r#"[{"op": "remove", "path": "a"}]"#).is_err());
+        assert!(parse(r#"[{"op": "add", "path": "/a"}]"#).is_err());
+        assert!(parse("[").is_err());
+    }
+
+    #[test]
+    fn json_pointers() {
+        let keys = path(&["a/b", "c~d", "~1", ""]);
+        let pointer = KeyPathFormat::JsonPointer.format(&keys, &Value::Boolean(false));
+        assert_eq!(pointer, "/a~1b/c~0d/~01/");
+        assert_eq!(KeyPathFormat::JsonPointer.split(&pointer), Some(keys));
+        assert_eq!(KeyPathFormat::JsonPointer.split(""), Some(Vec::new()));
+        assert_eq!(KeyPathFormat::JsonPointer.split("a"), None);
+
+        let from = dict([("a/b", Value::from(1))]);
+        let to = dict([("a/b", Value::from(2))]);
+        let text = export(&from, &to, PatchFormat::JsonPatch).unwrap();
+        let ops = serde_json::from_str::<serde_json::Value>(&text).unwrap();
+        assert_eq!(ops, json!([{"op": "replace", "path": "/a~1b", "value": 2}]));
+    }
+
+    #[test]
+    fn plistbuddy_script() {
+        let script = "#!/bin/sh\nset -e\n\
+            Set :a 7\n\
+            Set :b hello world\n\
+            Add :c:0 integer 9\n\
+            Print\n\
+            Delete :missing\n\
+            Set :c notanumber\n\
+            /usr/libexec/PlistBuddy -c 'Add :sub:k\\:\\\\ string \"q\"' \"$1\"\n";
+        let (value, outcome) = applied(script, &from());
+        assert_eq!(outcome.applied, 4, "{:?}", outcome.conflicts);
+        assert_eq!(outcome.conflicts.len(), 2);
+        let value = value.as_dictionary().unwrap();
+        assert_eq!(value["a"], Value::from(7));
+        assert_eq!(value["b"], Value::from("hello world"));
+        assert_eq!(value["c"].as_array().unwrap()[0], Value::from(9));
+        assert_eq!(
+            value["sub"].as_dictionary().unwrap().get(r"k:\"),
+            Some(&Value::from("q"))
+        );
+
+        // Other shell lines are skipped, but not unknown commands passed with -c.
+        assert_eq!(parse("echo Frob :a"), Ok(Vec::new()));
+        assert!(parse("PlistBuddy -c 'Frob :a' File.plist").is_err());
+        assert!(parse("Add :a integer x").is_err());
+        assert!(parse("Add a integer 1").is_err());
+    }
+
+    #[test]
+    fn words() {
+        assert_eq!(
+            shell_words(r#"a 'b c'  "d \"e\" \$f \g" h\ i 'j'"k""#),
+            Some(path(&["a", "b c", r#"d "e" $f \g"#, "h i", "jk"]))
+        );
+        assert_eq!(shell_words("''"), Some(path(&[""])));
+        assert_eq!(shell_words("'it'\\''s'"), Some(path(&["it's"])));
+        assert_eq!(shell_words("'a"), None);
+        assert_eq!(shell_words("\"a"), None);
+        assert_eq!(shell_words("a\\"), None);
+
+        let words = plistbuddy_words(r#"Set ":a\:b c" "x \" y" \\"#).unwrap();
+        assert_eq!(words, path(&["Set", r":a\:b c", r#"x \" y"#, r"\\"]));
+        assert_eq!(
+            words.iter().map(|v| unescape(v)).collect::<Vec<_>>(),
+            path(&["Set", ":a:b c", r#"x " y"#, r"\"])
+        );
+        assert_eq!(plistbuddy_words(r#"Set "a"#), None);
+        assert_eq!(plistbuddy_words("Set a\\"), None);
+    }
+
+    #[test]
+    fn json_values() {
+        let value = dict([
+            ("data", Value::Data(vec![0, 1, 0xFF])),
+            (
+                "date",
+                Value::Date(Date::from_xml_format("2024-01-02T03:04:05Z").unwrap()),
+            ),
+            ("nan", Value::Real(f64::NAN)),
+            ("inf", Value::Real(f64::NEG_INFINITY)),
+            ("real", Value::Real(0.5)),
+            ("uid", Value::Uid(Uid::new(7))),
+            ("big", Value::Integer(u64::MAX.into())),
+            ("array", Value::Array(vec![Value::Boolean(false)])),
+        ]);
+        let json = to_json(&value);
+        assert_eq!(
+            json,
+            json!({
+                "data": {"$data": "AAH/"},
+                "date": {"$date": "2024-01-02T03:04:05Z"},
+                "nan": {"$real": "nan"},
+                "inf": {"$real": "-inf"},
+                "real": 0.5,
+                "uid": {"$uid": 7},
+                "big": u64::MAX,
+                "array": [false],
+            })
+        );
+        let back = from_json(&json).unwrap();
+        let back = back.as_dictionary().unwrap();
+        assert!(back["nan"].as_real().unwrap().is_nan());
+        for (k, v) in value.as_dictionary().unwrap() {
+            if k != "nan" {
+                assert_eq!(&back[k], v, "{k}");
+            }
+        }
+
+        // Objects that only look tagged stay dictionaries.
+        assert_eq!(
+            from_json(&json!({"$other": "x"})),
+            Ok(dict([("$other", Value::from("x"))]))
+        );
+        assert_eq!(
+            from_json(&json!({"$data": 1})),
+            Ok(dict([("$data", Value::from(1))]))
+        );
+        assert!(from_json(&json!({"$data": "not base64!"})).is_err());
+        assert!(from_json(&json!({"$date": "yesterday"})).is_err());
+        assert!(from_json(&json!(null)).is_err());
+    }
+}
@@ -0,0 +1,447 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::{collections::HashMap, sync::Arc};
+
+use egui::Modifiers;
+use plist::{Dictionary, Value};
+
+use crate::{
+    convert::{PendingConversion, conversions},
+    document::Command,
+    utils::{ValueType, cmp_paths, duplicate, pv, try_pv, unique_key},
+};
+
+/// A cell within a row, for keyboard navigation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Column {
+    #[default]
+    Key,
+    Type,
+    Value,
+}
+
+impl Column {
+    #[must_use]
+    pub const fn next(self, backwards: bool) -> Self {
+        match (self, backwards) {
+            (Self::Key, false) | (Self::Value, true) => Self::Type,
+            (Self::Type, false) | (Self::Key, true) => Self::Value,
+            (Self::Value, false) | (Self::Type, true) => Self::Key,
+        }
+    }
+}
+
+/// Selected rows, plus the order rows are laid out in so Shift-click can select a range.
+///
+/// The cursor is the row the keyboard acts on, normally the last one clicked.
+#[derive(Default)]
+pub struct Selection {
+    paths: Vec<Vec<String>>,
+    anchor: Option<Vec<String>>,
+    cursor: Option<Vec<String>>,
+    column: Column,
+    /// Set when the cursor's cell should start editing once it's next shown.
+    edit: bool,
+    rows: Arc<Vec<Vec<String>>>,
+}
+
+impl Selection {
+    /// Sets the order rows are laid out in, whenever the tree is rebuilt.
+    pub fn set_rows(&mut self, rows: Arc<Vec<Vec<String>>>) {
+        self.rows = rows;
+    }
+
+    #[must_use]
+    pub fn contains(&self, path: &[String]) -> bool {
+        self.paths.iter().any(|v| v == path)
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.paths.clear();
+        self.anchor = None;
+        self.cursor = None;
+        self.edit = false;
+    }
+
+    pub fn set(&mut self, path: Vec<String>) {
+        self.paths = vec![path.clone()];
+        self.anchor = Some(path.clone());
+        self.cursor = Some(path);
+    }
+
+    pub fn click(&mut self, path: Vec<String>, modifiers: Modifiers) {
+        self.cursor = Some(path.clone());
+        if modifiers.shift
+            && let Some(anchor) = &self.anchor
+            && let Some(from) = self.rows.iter().position(|v| v == anchor)
+            && let Some(to) = self.rows.iter().position(|v| *v == path)
+        {
+            self.paths = self.rows[from.min(to)..=from.max(to)].to_vec();
+        } else if modifiers.command {
+            if let Some(i) = self.paths.iter().position(|v| *v == path) {
+                self.paths.remove(i);
+            } else {
+                self.paths.push(path.clone());
+            }
+            self.anchor = Some(path);
+        } else {
+            self.set(path);
+        }
+    }
+
+    #[must_use]
+    pub fn cursor(&self) -> Option<&[String]> {
+        self.cursor.as_deref()
+    }
+
+    #[must_use]
+    pub const fn column(&self) -> Column {
+        self.column
+    }
+
+    #[must_use]
+    pub fn is_cursor(&self, path: &[String], column: Column) -> bool {
+        self.column == column && self.cursor.as_deref() == Some(path)
+    }
+
+    pub const fn set_column(&mut self, column: Column) {
+        self.column = column;
+    }
+
+    /// Moves the cursor to `column` and starts editing it.
+    pub const fn request_edit(&mut self, column: Column) {
+        self.column = column;
+        self.edit = true;
+    }
+
+    /// The column to start editing, if `path` is the cursor and an edit was requested.
+    pub fn take_edit(&mut self, path: &[String]) -> Option<Column> {
+        (self.edit && self.cursor.as_deref() == Some(path)).then(|| {
+            self.edit = false;
+            self.column
+        })
+    }
+
+    /// Moves the cursor by `offset` rows, clamped to the first and last, optionally extending the
+    /// selection from the anchor. Returns the row moved to.
+    pub fn move_cursor(&mut self, offset: isize, extend: bool) -> Option<Vec<String>> {
+        let last = self.rows.len().checked_sub(1)?;
+        let i = self
+            .cursor
+            .as_ref()
+            .and_then(|v| self.row_index(v))
+            .map_or(0, |i| i.saturating_add_signed(offset).min(last));
+        let path = self.rows[i].clone();
+        if extend {
+            self.click(path.clone(), Modifiers::SHIFT);
+        } else {
+            self.set(path.clone());
+        }
+        Some(path)
+    }
+
+    /// The row laid out before `path`.
+    #[must_use]
+    pub fn row_before(&self, path: &[String]) -> Option<&[String]> {
+        let i = self.row_index(path)?.checked_sub(1)?;
+        Some(&self.rows[i])
+    }
+
+    /// Where `path` is laid out, counting rows from the top.
+    #[must_use]
+    pub fn row_index(&self, path: &[String]) -> Option<usize> {
+        self.rows.iter().position(|v| v == path)
+    }
+
+    fn position(&self, path: &[String]) -> usize {
+        self.row_index(path).unwrap_or(usize::MAX)
+    }
+
+    /// Selected paths that still exist, in row order, leaving out anything whose ancestor is
+    /// selected too.
+    #[must_use]
+    pub fn roots(&self, p: &Value) -> Vec<Vec<String>> {
+        let mut ret = self
+            .paths
+            .iter()
+            .filter(|v| try_pv(v, p).is_some())
+            .filter(|v| {
+                !self
+                    .paths
+                    .iter()
+                    .any(|a| a.len() < v.len() && v.starts_with(a))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        ret.sort_by(|a, b| {
+            self.position(a)
+                .cmp(&self.position(b))
+                .then_with(|| cmp_paths(a, b))
+        });
+        ret
+    }
+
+    #[must_use]
+    pub fn last(&self, p: &Value) -> Option<Vec<String>> {
+        self.roots(p).pop()
+    }
+
+    /// Removes the selected rows, deepest and last first so no removal moves another.
+    pub fn remove(&mut self, p: &Value) -> Option<Command> {
+        let mut paths = self.roots(p);
+        paths.retain(|v| !v.is_empty());
+        paths.sort_by(|a, b| cmp_paths(b, a));
+        self.clear();
+        Command::batch(
+            paths
+                .into_iter()
+                .map(|path| Command::Remove { path })
+                .collect(),
+        )
+    }
+
+    #[must_use]
+    pub fn duplicate(&self, p: &Value) -> Option<Command> {
+        // Copies added to the same array go after each other, not each at its original end.
+        let mut appended = HashMap::<&[String], usize>::new();
+        let roots = self.roots(p);
+        let mut commands = Vec::new();
+        for path in &roots {
+            let Some(mut command) = duplicate(path, p) else {
+                continue;
+            };
+            if let Command::Insert { path: new, .. } = &mut command
+                && let Some(Value::Array(v)) = try_pv(&path[..path.len() - 1], p)
+            {
+                let n = appended.entry(&path[..path.len() - 1]).or_default();
+                *new.last_mut().unwrap() = (v.len() + *n).to_string();
+                *n += 1;
+            }
+            commands.push(command);
+        }
+        Command::batch(commands)
+    }
+
+    #[must_use]
+    pub fn type_conversion(&self, p: &Value, ty: ValueType) -> PendingConversion {
+        PendingConversion {
+            to: Some(ty),
+            targets: self
+                .roots(p)
+                .into_iter()
+                .filter(|v| !v.is_empty() || ty.is_expandable())
+                .filter(|v| ValueType::from_val(v, p) != ty)
+                .map(|v| {
+                    let key = v.last().map_or("New Child", String::as_str);
+                    let options = conversions(pv(&v, p), key, ty);
+                    (v, options)
+                })
+                .collect(),
+        }
+    }
+
+    /// Parses `s` separately for each selected scalar, skipping those it isn't valid for.
+    #[must_use]
+    pub fn set_value(&self, p: &Value, s: &str) -> Option<Command> {
+        Command::batch(
+            self.roots(p)
+                .into_iter()
+                .filter_map(|path| {
+                    let value = pv(&path, p);
+                    let new = ValueType::from_val(&[], value).parse_value(s)?;
+                    (*value != new).then_some(Command::Set { path, value: new })
+                })
+                .collect(),
+        )
+    }
+
+    /// Replaces the selection with a new container holding it, placed where the first selected
+    /// row was.
+    pub fn move_into(&mut self, p: &Value, ty: ValueType) -> Option<Command> {
+        let mut paths = self.roots(p);
+        paths.retain(|v| !v.is_empty());
+        let first = paths.first()?.clone();
+        let items = paths
+            .iter()
+            .map(|v| (v.last().unwrap().clone(), pv(v, p).clone()))
+            .collect::<Vec<_>>();
+        let container = match ty {
+            ValueType::Array => Value::Array(items.into_iter().map(|(_, v)| v).collect()),
+            ValueType::Dictionary => {
+                let mut dict = Dictionary::new();
+                for (k, v) in items {
+                    dict.insert(unique_key(&dict, &k), v);
+                }
+                Value::Dictionary(dict)
+            }
+            _ => return None,
+        };
+        let Command::Batch(mut commands) = self.remove(p)? else {
+            unreachable!();
+        };
+
+        // The parent of the first row as it is once the selection is gone. Rows below it may
+        // have been removed too.
+        let (k, parent) = first.split_last().unwrap();
+        let mut siblings = pv(parent, p).clone();
+        for command in &commands {
+            if let Command::Remove { path } = command
+                && let Some(path) = path.strip_prefix(parent)
+            {
+                Command::Remove {
+                    path: path.to_vec(),
+                }
+                .apply(&mut siblings);
+            }
+        }
+        let (key, command) = match siblings {
+            // Inserting would put the container last, so the parent is set with it in place of
+            // the first row, keeping the rest in their order.
+            Value::Dictionary(v) => {
+                let key = unique_key(&v, "New Child");
+                let mut container = Some(container);
+                let mut dict = Dictionary::new();
+                for name in pv(parent, p).as_dictionary().unwrap().keys() {
+                    if name == k
+                        && let Some(container) = container.take()
+                    {
+                        dict.insert(key.clone(), container);
+                    }
+                    if let Some(v) = v.get(name) {
+                        dict.insert(name.clone(), v.clone());
+                    }
+                }
+                let command = Command::Set {
+                    path: parent.to_vec(),
+                    value: Value::Dictionary(dict),
+                };
+                (key, command)
+            }
+            Value::Array(v) => {
+                let key = k.parse::<usize>().map_or(v.len(), |i| i.min(v.len()));
+                let mut path = parent.to_vec();
+                path.push(key.to_string());
+                let command = Command::Insert {
+                    path,
+                    value: container,
+                };
+                (key.to_string(), command)
+            }
+            _ => unreachable!(),
+        };
+        commands.push(command);
+        let mut path = parent.to_vec();
+        path.push(key);
+        self.set(path);
+        Some(Command::Batch(commands))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use egui::Modifiers;
+    use plist::{Dictionary, Value};
+
+    use super::Selection;
+    use crate::utils::ValueType;
+
+    fn dict<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::Dictionary(Dictionary::from_iter(entries))
+    }
+
+    fn path(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|&v| v.to_owned()).collect()
+    }
+
+    fn keys(value: &Value) -> Vec<String> {
+        value.as_dictionary().unwrap().keys().cloned().collect()
+    }
+
+    fn select(rows: &[&[&str]]) -> Selection {
+        let mut ret = Selection::default();
+        ret.set_rows(Arc::new(rows.iter().map(|v| path(v)).collect()));
+        for row in rows {
+            ret.click(path(row), Modifiers::COMMAND);
+        }
+        ret
+    }
+
+    /// Moves the selected rows into a container, checking it's undone exactly.
+    fn move_into(root: &Value, rows: &[&[&str]], ty: ValueType) -> (Value, Vec<String>) {
+        let mut selection = select(rows);
+        let command = selection.move_into(root, ty).unwrap();
+        let mut value = root.clone();
+        let inverse = command.apply(&mut value).unwrap();
+        let mut undone = value.clone();
+        inverse.apply(&mut undone).unwrap();
+        assert_eq!(undone, *root);
+        assert_eq!(keys(&undone), keys(root));
+        (value, selection.cursor().unwrap().to_vec())
+    }
+
+    #[test]
+    fn move_into_dictionary() {
+        let root = dict([
+            ("a", Value::from(0)),
+            ("b", Value::from(1)),
+            ("c", Value::from(2)),
+            ("d", Value::from(3)),
+        ]);
+        let (value, cursor) = move_into(&root, &[&["b"], &["d"]], ValueType::Dictionary);
+        assert_eq!(keys(&value), path(&["a", "New Child", "c"]));
+        assert_eq!(cursor, path(&["New Child"]));
+        assert_eq!(
+            value.as_dictionary().unwrap()["New Child"],
+            dict([("b", Value::from(1)), ("d", Value::from(3))])
+        );
+    }
+
+    #[test]
+    fn move_into_taken_key() {
+        let root = dict([
+            ("New Child", Value::from(0)),
+            ("b", Value::from(1)),
+            ("c", dict([("New Child", Value::from(2))])),
+        ]);
+        let (value, cursor) = move_into(&root, &[&["b"], &["c", "New Child"]], ValueType::Array);
+        assert_eq!(
+            keys(&value),
+            path(&["New Child", "New Child Duplicate", "c"])
+        );
+        assert_eq!(cursor, path(&["New Child Duplicate"]));
+        let value = value.as_dictionary().unwrap();
+        assert_eq!(
+            value["New Child Duplicate"],
+            Value::Array(vec![Value::from(1), Value::from(2)])
+        );
+        // Rows below the parent that were moved are gone from it.
+        assert_eq!(value["c"], dict([]));
+
+        let (value, cursor) = move_into(&root, &[&["New Child"]], ValueType::Array);
+        assert_eq!(keys(&value), path(&["New Child", "b", "c"]));
+        assert_eq!(cursor, path(&["New Child"]));
+    }
+
+    #[test]
+    fn move_into_array() {
+        let root = dict([("a", Value::Array((0..4).map(Value::from).collect()))]);
+        let (value, cursor) = move_into(&root, &[&["a", "1"], &["a", "3"]], ValueType::Array);
+        assert_eq!(cursor, path(&["a", "1"]));
+        assert_eq!(
+            value.as_dictionary().unwrap()["a"],
+            Value::Array(vec![
+                Value::from(0),
+                Value::Array(vec![Value::from(1), Value::from(3)]),
+                Value::from(2),
+            ])
+        );
+    }
+}
@@ -1,7 +1,11 @@
 //! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
 //! See LICENSE for details.
 
-use plist::Value;
+use std::{cmp::Ordering, time::SystemTime};
+
+use plist::{Dictionary, Integer, Value};
+
+use crate::document::Command;
 
 #[must_use]
 pub fn pv<'a>(path: &[String], mut p: &'a Value) -> &'a Value {
@@ -16,15 +20,116 @@ pub fn pv<'a>(path: &[String], mut p: &'a Value) -> &'a Value {
 }
 
 #[must_use]
-pub fn pv_mut<'a>(path: &[String], mut p: &'a mut Value) -> &'a mut Value {
+pub fn try_pv<'a>(path: &[String], mut p: &'a Value) -> Option<&'a Value> {
     for k in path {
         p = match p {
-            Value::Dictionary(v) => &mut v[k],
-            Value::Array(v) => &mut v[k.parse::<usize>().unwrap()],
-            _ => unreachable!(),
+            Value::Dictionary(v) => v.get(k)?,
+            Value::Array(v) => v.get(k.parse::<usize>().ok()?)?,
+            _ => return None,
         };
     }
-    p
+    Some(p)
+}
+
+#[must_use]
+pub fn try_pv_mut<'a>(path: &[String], mut p: &'a mut Value) -> Option<&'a mut Value> {
+    for k in path {
+        p = match p {
+            Value::Dictionary(v) => v.get_mut(k)?,
+            Value::Array(v) => v.get_mut(k.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(p)
+}
+
+/// Copies the node at `path` to the end of its parent, under a key of its own.
+#[must_use]
+pub fn duplicate(path: &[String], p: &Value) -> Option<Command> {
+    let (k, parent) = path.split_last()?;
+    let key = match try_pv(parent, p)? {
+        Value::Dictionary(_) => k.clone(),
+        Value::Array(v) => v.len().to_string(),
+        _ => return None,
+    };
+    Some(Command::Insert {
+        path: parent.iter().cloned().chain(std::iter::once(key)).collect(),
+        value: try_pv(path, p)?.clone(),
+    })
+}
+
+/// Adds an empty string to the container at `path`, returning the command and the key it will
+/// have.
+#[must_use]
+pub fn add_child(path: &[String], p: &Value) -> Option<(Command, String)> {
+    let key = match try_pv(path, p)? {
+        Value::Dictionary(v) => unique_key(v, "New Child"),
+        Value::Array(v) => v.len().to_string(),
+        _ => return None,
+    };
+    let command = Command::Insert {
+        path: path
+            .iter()
+            .cloned()
+            .chain(std::iter::once(key.clone()))
+            .collect(),
+        value: Value::String(String::new()),
+    };
+    Some((command, key))
+}
+
+#[must_use]
+pub fn unique_key(dict: &Dictionary, k: &str) -> String {
+    let mut key = k.to_owned();
+    while dict.contains_key(&key) {
+        key = format!("{key} Duplicate");
+    }
+    key
+}
+
+/// Renames a key in place. Inserting appends, and removing swaps the last entry into the freed
+/// slot, so the renamed entry ends up where the old one was.
+pub fn rename_key(dict: &mut Dictionary, from: &str, to: String) -> bool {
+    if from == to || dict.contains_key(&to) {
+        return false;
+    }
+    let Some(value) = dict.get(from).cloned() else {
+        return false;
+    };
+    dict.insert(to, value);
+    dict.remove(from);
+    true
+}
+
+/// Whether two values are equal with their dictionary entries in the same order, which `==`
+/// doesn't look at.
+#[must_use]
+pub fn identical(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Dictionary(a), Value::Dictionary(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b)
+                    .all(|((ka, a), (kb, b))| ka == kb && identical(a, b))
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| identical(a, b))
+        }
+        _ => a == b,
+    }
+}
+
+/// Orders paths the way rows are laid out, comparing array indices numerically.
+#[must_use]
+pub fn cmp_paths(a: &[String], b: &[String]) -> Ordering {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| match (a.parse::<usize>(), b.parse::<usize>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            _ => a.cmp(b),
+        })
+        .find(|v| v.is_ne())
+        .unwrap_or_else(|| a.len().cmp(&b.len()))
 }
 
 #[must_use]
@@ -36,6 +141,58 @@ pub fn child_keys(path: &[String], p: &Value) -> Vec<String> {
     }
 }
 
+#[must_use]
+pub fn int_to_i128(v: Integer) -> i128 {
+    v.as_signed()
+        .map_or_else(|| i128::from(v.as_unsigned().unwrap()), i128::from)
+}
+
+/// Plist integers span `i64::MIN..=u64::MAX`, so anything in that range fits.
+#[must_use]
+pub fn int_from_i128(v: i128) -> Option<Integer> {
+    i64::try_from(v)
+        .map(Integer::from)
+        .or_else(|_| u64::try_from(v).map(Integer::from))
+        .ok()
+}
+
+/// Parses decimal, `0x`, `0o` or `0b` integers, with an optional sign and `_` separators.
+#[must_use]
+pub fn parse_integer(s: &str) -> Option<Integer> {
+    let s = s.trim().replace('_', "");
+    let (negative, s) = s
+        .strip_prefix('-')
+        .map_or((false, s.as_str()), |v| (true, v));
+    let s = s.strip_prefix('+').unwrap_or(s);
+    let (radix, digits) = match s.get(..2).map(str::to_ascii_lowercase).as_deref() {
+        Some("0x") => (16, &s[2..]),
+        Some("0o") => (8, &s[2..]),
+        Some("0b") => (2, &s[2..]),
+        _ => (10, s),
+    };
+    if digits.is_empty() || digits.starts_with(['+', '-']) {
+        return None;
+    }
+    let v = i128::try_from(u128::from_str_radix(digits, radix).ok()?).ok()?;
+    int_from_i128(if negative { -v } else { v })
+}
+
+/// Accepts anything `f64` parses, which includes exponents, `nan`, `inf` and `-inf`.
+#[must_use]
+pub fn parse_real(s: &str) -> Option<f64> {
+    s.trim().parse::<f64>().ok()
+}
+
+/// The shortest text that parses back to exactly `v`.
+#[must_use]
+pub fn format_real(v: f64) -> String {
+    if v.is_nan() {
+        "nan".into()
+    } else {
+        format!("{v:?}")
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ValueType {
     Array,
@@ -49,6 +206,17 @@ pub enum ValueType {
 }
 
 impl ValueType {
+    pub const ALL: [Self; 8] = [
+        Self::Array,
+        Self::Dictionary,
+        Self::Boolean,
+        Self::Data,
+        Self::Date,
+        Self::Real,
+        Self::Integer,
+        Self::String,
+    ];
+
     #[must_use]
     pub fn from_val(path: &[String], p: &Value) -> Self {
         match pv(path, p) {
@@ -68,4 +236,123 @@ impl ValueType {
     pub const fn is_expandable(self) -> bool {
         matches!(self, Self::Array | Self::Dictionary)
     }
+
+    #[must_use]
+    pub fn default_value(self) -> Value {
+        match self {
+            Self::Array => Value::Array(Default::default()),
+            Self::Dictionary => Value::Dictionary(Default::default()),
+            Self::Boolean => Value::Boolean(Default::default()),
+            Self::Data => Value::Data(Default::default()),
+            Self::Date => Value::Date(SystemTime::now().into()),
+            Self::Real => Value::Real(Default::default()),
+            Self::Integer => Value::Integer(0.into()),
+            Self::String => Value::String(Default::default()),
+        }
+    }
+
+    /// Parses user input into a value of this type, the same way the value column does.
+    #[must_use]
+    pub fn parse_value(self, s: &str) -> Option<Value> {
+        match self {
+            Self::Boolean => match s.trim().to_ascii_lowercase().as_str() {
+                "true" | "yes" | "1" => Some(Value::Boolean(true)),
+                "false" | "no" | "0" => Some(Value::Boolean(false)),
+                _ => None,
+            },
+            Self::Data => hex::decode(s).ok().map(Value::Data),
+            Self::Date => plist::Date::from_xml_format(s.trim()).ok().map(Value::Date),
+            Self::Real => parse_real(s).map(Value::Real),
+            Self::Integer => parse_integer(s).map(Value::Integer),
+            Self::String => Some(Value::String(s.to_owned())),
+            Self::Array | Self::Dictionary => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plist::Integer;
+
+    use super::{format_real, int_from_i128, int_to_i128, parse_integer, parse_real};
+
+    fn parsed(s: &str) -> Option<i128> {
+        parse_integer(s).map(int_to_i128)
+    }
+
+    #[test]
+    fn radixes() {
+        assert_eq!(parsed("42"), Some(42));
+        assert_eq!(parsed("0x2a"), Some(42));
+        assert_eq!(parsed("0X2A"), Some(42));
+        assert_eq!(parsed("0o52"), Some(42));
+        assert_eq!(parsed("0b10_1010"), Some(42));
+        assert_eq!(parsed(" 1_000 "), Some(1000));
+        assert_eq!(parsed("0xFFFF_FFFF"), Some(0xffff_ffff));
+        for s in ["", "0x", "x2a", "0b2", "0o8", "4 2", "1.0", "--1", "+-1"] {
+            assert_eq!(parsed(s), None, "{s:?}");
+        }
+    }
+
+    #[test]
+    fn signs() {
+        assert_eq!(parsed("+42"), Some(42));
+        assert_eq!(parsed("-42"), Some(-42));
+        assert_eq!(parsed("-0x2a"), Some(-42));
+        assert_eq!(parsed("+0b1"), Some(1));
+        assert_eq!(parsed("-0"), Some(0));
+    }
+
+    #[test]
+    fn range() {
+        let max = i128::from(u64::MAX);
+        let min = i128::from(i64::MIN);
+        assert_eq!(parsed("18446744073709551615"), Some(max));
+        assert_eq!(parsed("0xffffffffffffffff"), Some(max));
+        assert_eq!(parsed("-9223372036854775808"), Some(min));
+        assert_eq!(parsed("-0x8000000000000000"), Some(min));
+        for s in [
+            "18446744073709551616",
+            "0x1_0000_0000_0000_0000",
+            "-9223372036854775809",
+            "-18446744073709551615",
+            "999999999999999999999999999999999999999999",
+        ] {
+            assert_eq!(parsed(s), None, "{s}");
+        }
+
+        let above = i128::from(i64::MAX) + 1;
+        for v in [min, -1, 0, i128::from(i64::MAX), above, max - 1, max] {
+            let int = int_from_i128(v).unwrap();
+            assert_eq!(int_to_i128(int), v);
+            assert_eq!(parsed(&int.to_string()), Some(v));
+        }
+        assert_eq!(int_from_i128(above), Some(Integer::from(1_u64 << 63)));
+        assert_eq!(int_from_i128(min - 1), None);
+        assert_eq!(int_from_i128(max + 1), None);
+    }
+
+    #[test]
+    fn reals() {
+        for v in [
+            0.0,
+            -0.0,
+            0.1,
+            1.0 / 3.0,
+            1e300,
+            -2.5e-300,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ] {
+            let s = format_real(v);
+            assert_eq!(parse_real(&s).map(f64::to_bits), Some(v.to_bits()), "{s}");
+        }
+        assert_eq!(format_real(1.0), "1.0");
+        assert_eq!(format_real(0.1), "0.1");
+        assert!(parse_real(&format_real(f64::NAN)).unwrap().is_nan());
+        assert_eq!(parse_real(" 1e3 "), Some(1000.0));
+        assert_eq!(parse_real("1,5"), None);
+    }
 }
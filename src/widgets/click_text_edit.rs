@@ -17,6 +17,29 @@ fn set(get_set_value: &mut GetSetValue<'_>, value: String) {
     (get_set_value)(Some(value));
 }
 
+fn edit_request_id() -> Id {
+    Id::new("ClickableTextEditRequest")
+}
+
+fn tab_id() -> Id {
+    Id::new("ClickableTextEditTab")
+}
+
+/// Makes the next [`ClickableTextEdit`] shown start editing, as a double-click would.
+pub fn request_edit(ctx: &Context) {
+    ctx.data_mut(|d| d.insert_temp(edit_request_id(), true));
+}
+
+/// Drops a request no [`ClickableTextEdit`] was shown for.
+pub fn cancel_edit_request(ctx: &Context) {
+    ctx.data_mut(|d| d.remove::<bool>(edit_request_id()));
+}
+
+/// Whether an edit was just finished with Tab, and if so whether Shift was held.
+pub fn take_tab(ctx: &Context) -> Option<bool> {
+    ctx.data_mut(|d| d.remove_temp(tab_id()))
+}
+
 #[derive(Clone, Debug, Default, PartialEq)]
 struct State {
     edit_string: String,
@@ -80,6 +103,7 @@ impl Widget for ClickableTextEdit<'_> {
                 TextEdit::singleline(&mut state.edit_string)
                     .desired_width(f32::INFINITY)
                     .id(kb_edit_id)
+                    .lock_focus(true)
                     .font(TextStyle::Monospace),
             );
 
@@ -95,19 +119,32 @@ impl Widget for ClickableTextEdit<'_> {
                     ui.label(RichText::new("Invalid").color(Color32::RED).strong());
                 });
 
-            if validate_value(state.edit_string.as_str()) {
-                egui::Popup::close_id(ui.ctx(), popup_id);
-                if ui.input(|v| v.key_pressed(Key::Enter)) {
-                    set(&mut get_set_value, state.edit_string.clone());
-                    ui.memory_mut(|v| v.surrender_focus(kb_edit_id));
-                    ui.data_mut(|d| d.remove::<State>(state_id));
-                    return response;
-                }
-            } else {
+            let (enter, tab, shift) = ui.input(|v| {
+                (
+                    v.key_pressed(Key::Enter),
+                    v.key_pressed(Key::Tab),
+                    v.modifiers.shift,
+                )
+            });
+            if !validate_value(state.edit_string.as_str()) {
                 egui::Popup::open_id(ui.ctx(), popup_id);
                 ui.memory_mut(|v| v.request_focus(kb_edit_id));
+                state.store(ui.ctx(), state_id);
+            } else if enter || tab {
+                // Falls through so the committed value is reported as a change below.
+                egui::Popup::close_id(ui.ctx(), popup_id);
+                set(&mut get_set_value, state.edit_string);
+                ui.memory_mut(|v| v.surrender_focus(kb_edit_id));
+                ui.data_mut(|d| {
+                    d.remove::<State>(state_id);
+                    if tab {
+                        d.insert_temp(tab_id(), shift);
+                    }
+                });
+            } else {
+                egui::Popup::close_id(ui.ctx(), popup_id);
+                state.store(ui.ctx(), state_id);
             }
-            state.store(ui.ctx(), state_id);
             response
         } else {
             let mut s = old_value.as_str();
@@ -118,7 +155,8 @@ impl Widget for ClickableTextEdit<'_> {
 
             let response = ui.add(textedit).on_hover_cursor(CursorIcon::Text);
 
-            if response.double_clicked() {
+            let requested = ui.data_mut(|d| d.remove_temp::<bool>(edit_request_id()).is_some());
+            if response.double_clicked() || requested {
                 ui.memory_mut(|v| v.request_focus(kb_edit_id));
                 ui.data_mut(|d| d.remove::<State>(state_id));
             }
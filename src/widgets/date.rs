@@ -0,0 +1,174 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::time::SystemTime;
+
+use chrono::{
+    DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Timelike, Utc,
+};
+use egui::{Align, Context, DragValue, Id, Layout, Response, Ui, Widget};
+use egui_extras::DatePickerButton;
+
+use super::click_text_edit::ClickableTextEdit;
+
+fn local_id(id: Id) -> Id {
+    id.with("local_date")
+}
+
+/// Whether the date row `id` is shown in the local time zone instead of UTC.
+#[must_use]
+pub fn show_local(ctx: &Context, id: Id) -> bool {
+    ctx.data_mut(|d| d.get_persisted(local_id(id)).unwrap_or_default())
+}
+
+pub fn set_show_local(ctx: &Context, id: Id, local: bool) {
+    ctx.data_mut(|d| d.insert_persisted(local_id(id), local));
+}
+
+fn to_utc(date: plist::Date) -> DateTime<Utc> {
+    SystemTime::from(date).into()
+}
+
+fn from_utc(date: DateTime<Utc>) -> plist::Date {
+    SystemTime::from(date).into()
+}
+
+#[must_use]
+pub fn format(date: plist::Date, local: bool) -> String {
+    let date = to_utc(date);
+    if local {
+        date.with_timezone(&Local)
+            .to_rfc3339_opts(SecondsFormat::AutoSi, false)
+    } else {
+        date.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+    }
+}
+
+fn to_naive(date: plist::Date, local: bool) -> NaiveDateTime {
+    let date = to_utc(date);
+    if local {
+        date.with_timezone(&Local).naive_local()
+    } else {
+        date.naive_utc()
+    }
+}
+
+fn from_naive(date: NaiveDateTime, local: bool) -> Option<plist::Date> {
+    let date = if local {
+        Local
+            .from_local_datetime(&date)
+            .earliest()?
+            .with_timezone(&Utc)
+    } else {
+        Utc.from_utc_datetime(&date)
+    };
+    Some(from_utc(date))
+}
+
+/// Parses ISO 8601 input. Text without an offset is taken to be in the displayed time zone.
+#[must_use]
+pub fn parse(s: &str, local: bool) -> Option<plist::Date> {
+    let s = s.trim();
+    if let Ok(v) = DateTime::parse_from_rfc3339(s) {
+        return Some(from_utc(v.with_timezone(&Utc)));
+    }
+    let date = [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+    ]
+    .iter()
+    .find_map(|v| NaiveDateTime::parse_from_str(s, v).ok())
+    .or_else(|| {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .ok()
+            .map(|v| v.and_time(NaiveTime::MIN))
+    })?;
+    from_naive(date, local)
+}
+
+#[must_use]
+pub struct DateEdit<'a> {
+    value: &'a mut plist::Date,
+    /// The row's id, which its time zone and date picker are kept under.
+    id: Id,
+}
+
+impl<'a> DateEdit<'a> {
+    pub const fn new(value: &'a mut plist::Date, id: Id) -> Self {
+        Self { value, id }
+    }
+}
+
+impl Widget for DateEdit<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let Self { value, id } = self;
+        let mut local = show_local(ui.ctx(), id);
+        let mut naive = to_naive(*value, local);
+        let mut picked = false;
+        let mut typed = false;
+
+        let mut response = ui
+            .with_layout(Layout::right_to_left(Align::Center), |ui| {
+                ui.menu_button("🕒", |ui| {
+                    ui.horizontal(|ui| {
+                        let (mut h, mut m, mut s) = (naive.hour(), naive.minute(), naive.second());
+                        let mut changed = ui.add(DragValue::new(&mut h).range(0..=23)).changed();
+                        ui.label(":");
+                        changed |= ui.add(DragValue::new(&mut m).range(0..=59)).changed();
+                        ui.label(":");
+                        changed |= ui.add(DragValue::new(&mut s).range(0..=59)).changed();
+                        if changed
+                            && let Some(v) =
+                                naive.date().and_hms_nano_opt(h, m, s, naive.nanosecond())
+                        {
+                            naive = v;
+                            picked = true;
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Show as");
+                        let prev = local;
+                        ui.selectable_value(&mut local, false, "UTC");
+                        ui.selectable_value(&mut local, true, "Local");
+                        if prev != local {
+                            set_show_local(ui.ctx(), id, local);
+                        }
+                    });
+                });
+
+                let mut date = naive.date();
+                // The picker's salt is a string, so it's kept apart from other rows' by the id.
+                let picker = ui.push_id(id, |ui| {
+                    ui.add(DatePickerButton::new(&mut date).id_salt("date").format(""))
+                });
+                if picker.inner.changed() {
+                    naive = date.and_time(naive.time());
+                    picked = true;
+                }
+                if picked && let Some(v) = from_naive(naive, local) {
+                    *value = v;
+                }
+
+                ui.add(ClickableTextEdit::from_get_set(
+                    |v| {
+                        if let Some(v) = v
+                            && let Some(v) = parse(&v, local)
+                            && v != *value
+                        {
+                            *value = v;
+                            typed = true;
+                        }
+                        format(*value, local)
+                    },
+                    |v| parse(v, local).is_some(),
+                    false,
+                ))
+            })
+            .inner;
+        if picked || typed {
+            response.mark_changed();
+        }
+        response
+    }
+}
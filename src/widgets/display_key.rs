@@ -0,0 +1,104 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::path::{Path, PathBuf};
+
+use egui::{Context, Id, Ui};
+use plist::Value;
+
+use crate::{find::value_text, utils::try_pv};
+
+/// Candidate keys offered for an array, taken from its dictionaries.
+const MAX_KEYS: usize = 32;
+/// Labels longer than this are cut short with an ellipsis.
+const MAX_LABEL_LEN: usize = 64;
+
+fn document_id() -> Id {
+    Id::new("DisplayKeyDocument")
+}
+
+/// Sets the file display keys are remembered for, so each document keeps its own.
+pub fn set_document(ctx: &Context, path: Option<&Path>) {
+    ctx.data_mut(|d| d.insert_temp(document_id(), path.map(Path::to_path_buf)));
+}
+
+fn key_id(ctx: &Context, scope: Option<Id>, path: &[String]) -> Id {
+    let document = ctx
+        .data_mut(|d| d.get_temp::<Option<PathBuf>>(document_id()))
+        .flatten();
+    Id::new((document, scope, path)).with("display_key")
+}
+
+/// The child key chosen to label the items of the array at `path`.
+#[must_use]
+pub fn get(ctx: &Context, scope: Option<Id>, path: &[String]) -> Option<String> {
+    ctx.data_mut(|d| d.get_persisted(key_id(ctx, scope, path)))
+        .flatten()
+}
+
+pub fn set(ctx: &Context, scope: Option<Id>, path: &[String], key: Option<String>) {
+    let id = key_id(ctx, scope, path);
+    ctx.data_mut(|d| d.insert_persisted(id, key));
+}
+
+fn text(v: &Value) -> Option<String> {
+    let text = match v {
+        Value::Boolean(v) => v.to_string(),
+        Value::Date(v) => v.to_xml_format(),
+        v => value_text(v)?,
+    };
+    let line = text.lines().next().unwrap_or_default();
+    Some(if line.chars().count() > MAX_LABEL_LEN {
+        format!("{}…", line.chars().take(MAX_LABEL_LEN).collect::<String>())
+    } else {
+        line.to_owned()
+    })
+}
+
+/// The label for the array item at `path`: its value for the array's display key, if it has one.
+#[must_use]
+pub fn label(ctx: &Context, scope: Option<Id>, path: &[String], p: &Value) -> Option<String> {
+    let (_, parent) = path.split_last()?;
+    if !matches!(try_pv(parent, p), Some(Value::Array(_))) {
+        return None;
+    }
+    let key = get(ctx, scope, parent)?;
+    try_pv(path, p)?.as_dictionary()?.get(&key).and_then(text)
+}
+
+/// Lets the user pick which child key labels the items of `array`.
+pub fn menu(ui: &mut Ui, scope: Option<Id>, path: &[String], array: &[Value]) {
+    let mut keys = Vec::new();
+    for k in array
+        .iter()
+        .filter_map(Value::as_dictionary)
+        .flat_map(|v| v.keys())
+    {
+        if keys.len() == MAX_KEYS {
+            break;
+        }
+        if !keys.contains(k) {
+            keys.push(k.clone());
+        }
+    }
+    if keys.is_empty() {
+        return;
+    }
+    ui.menu_button("Display Key", |ui| {
+        let current = get(ui.ctx(), scope, path);
+        if ui.selectable_label(current.is_none(), "None").clicked() {
+            set(ui.ctx(), scope, path, None);
+            ui.close();
+        }
+        ui.separator();
+        for k in keys {
+            if ui
+                .selectable_label(current.as_ref() == Some(&k), &k)
+                .clicked()
+            {
+                set(ui.ctx(), scope, path, Some(k));
+                ui.close();
+            }
+        }
+    });
+}
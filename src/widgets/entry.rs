@@ -1,18 +1,39 @@
 //! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
 //! See LICENSE for details.
 
-use std::{
-    sync::{Arc, Mutex},
-    time::SystemTime,
+use egui::{
+    Button, ComboBox, Context, Id, Key, KeyboardShortcut, Label, Modifiers, Response, Sense,
+    TextEdit, pos2, vec2,
 };
-
-use egui::{ComboBox, Context, Id, Label, Response, Sense, TextEdit, pos2, vec2};
-use egui_extras::TableBody;
+use egui_extras::TableRow;
 use plist::Value;
 use serde::{Deserialize, Serialize};
 
-use super::{click_text_edit::ClickableTextEdit, value::PlistValue};
-use crate::utils::{ValueType, child_keys, pv_mut};
+use super::{
+    click_text_edit::{self, ClickableTextEdit},
+    display_key, hex_editor,
+    image_preview::ImageKind,
+    table_view, tree,
+    value::PlistValue,
+};
+use crate::{
+    changes, clipboard,
+    convert::{self, PendingConversion, conversions},
+    data_file,
+    document::Command,
+    format::Format,
+    key_path::KeyPathFormat,
+    selection::{Column, Selection},
+    utils::{ValueType, add_child, duplicate, pv, try_pv},
+};
+
+/// Expands the selected rows and everything below them.
+pub const EXPAND_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::ALT, Key::ArrowRight);
+/// Collapses the selected rows and everything below them.
+pub const COLLAPSE_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::ALT, Key::ArrowLeft);
+const MAX_EXPAND_DEPTH: usize = 5;
 
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 struct State {
@@ -37,109 +58,390 @@ impl State {
     }
 }
 
-pub struct PlistEntry {
-    data: Arc<Mutex<Value>>,
-    path: Vec<String>,
-    id: Id,
+#[must_use]
+pub fn row_id(scope: Option<Id>, path: &[String]) -> Id {
+    scope.map_or_else(|| Id::new(path), |v| v.with(path))
+}
+
+#[must_use]
+pub fn is_expanded(ctx: &Context, path: &[String]) -> bool {
+    is_expanded_in(ctx, None, path)
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub enum ChangeState {
-    Unchanged,
-    Changed,
-    Removed,
+/// Whether the row at `path` is expanded, in the document nested under `scope` if set.
+#[must_use]
+pub fn is_expanded_in(ctx: &Context, scope: Option<Id>, path: &[String]) -> bool {
+    State::load(ctx, row_id(scope, path)).is_some_and(|v| v.expanded)
 }
 
-impl std::ops::BitOr for ChangeState {
-    type Output = Self;
+pub fn set_expanded(ctx: &Context, path: &[String], expanded: bool) {
+    State { expanded }.store(ctx, row_id(None, path));
+    tree::invalidate(ctx);
+}
 
-    fn bitor(self, rhs: Self) -> Self::Output {
-        self.max(rhs)
+/// Expands every ancestor of `path` so its row is shown.
+pub fn reveal(ctx: &Context, path: &[String]) {
+    for i in 0..path.len() {
+        let id = row_id(None, &path[..i]);
+        let mut state = State::load(ctx, id).unwrap_or_default();
+        state.expanded = true;
+        state.store(ctx, id);
     }
+    tree::invalidate(ctx);
 }
 
-impl std::ops::BitOrAssign for ChangeState {
-    fn bitor_assign(&mut self, rhs: Self) {
-        *self = *self | rhs
+/// Expands the containers at `path` and below that are fewer than `depth` levels down, and
+/// collapses the rest. `None` expands everything.
+pub fn expand_to_depth(
+    ctx: &Context,
+    scope: Option<Id>,
+    path: &[String],
+    p: &Value,
+    depth: Option<usize>,
+) {
+    fn walk(
+        ctx: &Context,
+        scope: Option<Id>,
+        path: &mut Vec<String>,
+        p: &Value,
+        level: usize,
+        depth: Option<usize>,
+    ) {
+        let children: Vec<(String, &Value)> = match p {
+            Value::Dictionary(v) => v.iter().map(|(k, v)| (k.clone(), v)).collect(),
+            Value::Array(v) => v
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i.to_string(), v))
+                .collect(),
+            _ => return,
+        };
+        let id = row_id(scope, path);
+        let mut state = State::load(ctx, id).unwrap_or_default();
+        state.expanded = depth.is_none_or(|v| level < v);
+        state.store(ctx, id);
+        for (k, v) in children {
+            path.push(k);
+            walk(ctx, scope, path, v, level + 1, depth);
+            path.pop();
+        }
     }
+    walk(ctx, scope, &mut path.to_vec(), pv(path, p), 0, depth);
+    tree::invalidate(ctx);
+}
+
+/// A single row of the [`Tree`](super::tree::Tree).
+pub struct PlistEntry<'a> {
+    data: &'a Value,
+    path: Vec<String>,
+    id: Id,
+    /// Set for documents nested inside a Data value, to keep their rows' ids apart.
+    scope: Option<Id>,
+    /// The Data values leading to the document, if it's a nested one.
+    nesting: &'a [Vec<String>],
+    indent: usize,
+    /// Whether the filter bar is showing matches, which are always shown expanded.
+    filtering: bool,
+    selection: &'a mut Selection,
 }
 
-impl PlistEntry {
-    pub fn new(data: Arc<Mutex<Value>>, path: Vec<String>) -> Self {
-        let id = Id::new(&path);
-        Self { data, path, id }
+impl<'a> PlistEntry<'a> {
+    pub fn new(
+        data: &'a Value,
+        path: Vec<String>,
+        scope: Option<Id>,
+        nesting: &'a [Vec<String>],
+        indent: usize,
+        filtering: bool,
+        selection: &'a mut Selection,
+    ) -> Self {
+        let id = row_id(scope, &path);
+        Self {
+            data,
+            path,
+            id,
+            scope,
+            nesting,
+            indent,
+            filtering,
+            selection,
+        }
+    }
+
+    /// Converts right away, unless it loses data or has several outcomes to pick from.
+    fn change_type(
+        ctx: &Context,
+        nesting: &[Vec<String>],
+        pending: PendingConversion,
+    ) -> Option<Command> {
+        if pending.needs_confirmation() {
+            convert::request(ctx, nesting, pending);
+            None
+        } else {
+            pending.apply(0)
+        }
     }
 
     #[must_use]
-    fn get_new_key(keys: plist::dictionary::Keys, k: &str) -> String {
-        keys.filter(|v| (v.as_str() == k) || (v.starts_with(k) && v.ends_with("Duplicate")))
-            .last()
-            .map_or_else(|| "New Child".into(), |v| format!("{v} Duplicate"))
+    fn render_bulk_menu(
+        ui: &mut egui::Ui,
+        nesting: &[Vec<String>],
+        p: &Value,
+        selection: &mut Selection,
+    ) -> Option<Command> {
+        let mut ret = None;
+        let roots = selection.roots(p);
+        let n = roots.len();
+
+        if ui
+            .add(
+                Button::new(format!("Copy {n} Items"))
+                    .shortcut_text(ui.ctx().format_shortcut(&clipboard::COPY_SHORTCUT)),
+            )
+            .clicked()
+        {
+            clipboard::copy(ui.ctx(), &roots, p, false);
+            ui.close();
+        }
+        if ui
+            .add(
+                Button::new(format!("Cut {n} Items"))
+                    .shortcut_text(ui.ctx().format_shortcut(&clipboard::CUT_SHORTCUT)),
+            )
+            .clicked()
+        {
+            ret = clipboard::copy(ui.ctx(), &roots, p, true).inspect(|_| selection.clear());
+            ui.close();
+        }
+
+        ui.separator();
+        if ui.button(format!("Duplicate {n} Items")).clicked() {
+            ret = selection.duplicate(p);
+            ui.close();
+        }
+        if ui.button(format!("Remove {n} Items")).clicked() {
+            ret = selection.remove(p);
+            ui.close();
+        }
+
+        ui.separator();
+        ui.menu_button("Change Type", |ui| {
+            for ty in ValueType::ALL {
+                if ui.button(format!("{ty:?}")).clicked() {
+                    ret = Self::change_type(ui.ctx(), nesting, selection.type_conversion(p, ty));
+                    ui.close();
+                }
+            }
+        });
+        ui.menu_button("Set Value", |ui| {
+            let id = ui.id().with("bulk_value");
+            let mut s = ui.data_mut(|d| d.get_temp::<String>(id).unwrap_or_default());
+            ui.add(TextEdit::singleline(&mut s).font(egui::TextStyle::Monospace));
+            if ui.button("Apply").clicked() {
+                ret = selection.set_value(p, &s);
+                ui.close();
+            }
+            ui.data_mut(|d| d.insert_temp(id, s));
+        });
+
+        ui.separator();
+        if ui.button("Move into New Array").clicked() {
+            ret = selection.move_into(p, ValueType::Array);
+            ui.close();
+        }
+        if ui.button("Move into New Dictionary").clicked() {
+            ret = selection.move_into(p, ValueType::Dictionary);
+            ui.close();
+        }
+
+        ret
     }
 
     #[must_use]
-    fn render_menu(resp: &Response, path: &[String], p: &mut Value) -> ChangeState {
-        let mut ret = ChangeState::Unchanged;
+    fn render_menu(
+        resp: &Response,
+        nesting: &[Vec<String>],
+        scope: Option<Id>,
+        path: &[String],
+        p: &Value,
+        selection: &mut Selection,
+    ) -> Option<Command> {
+        let mut ret = None;
+
+        if resp.secondary_clicked() && !selection.contains(path) {
+            selection.set(path.to_vec());
+        }
 
         egui::Popup::context_menu(resp).show(|ui| {
-            match ValueType::from_val(path, p) {
+            if selection.len() > 1 && selection.contains(path) {
+                ret = Self::render_bulk_menu(ui, nesting, p, selection);
+                return;
+            }
+
+            let ty = ValueType::from_val(path, p);
+            match ty {
                 ValueType::Dictionary => {
                     if ui.button("Add child").clicked() {
-                        let dict = pv_mut(path, p).as_dictionary_mut().unwrap();
-                        dict.insert(
-                            Self::get_new_key(dict.keys(), "New Child"),
-                            Value::String(String::new()),
-                        );
+                        ret = add_child(path, p).map(|(v, _)| v);
                         ui.close();
-                        ret |= ChangeState::Changed;
                     }
                     if ui.button("Sort").clicked() {
-                        pv_mut(path, p).as_dictionary_mut().unwrap().sort_keys();
+                        let mut value = pv(path, p).clone();
+                        value.as_dictionary_mut().unwrap().sort_keys();
+                        ret = Some(Command::Set {
+                            path: path.to_vec(),
+                            value,
+                        });
                         ui.close();
-                        ret |= ChangeState::Changed;
                     }
                 }
-                ValueType::Array if ui.button("Add child").clicked() => {
-                    pv_mut(path, p)
-                        .as_array_mut()
-                        .unwrap()
-                        .push(Value::String(String::new()));
-                    ui.close();
-                    ret |= ChangeState::Changed;
+                ValueType::Array => {
+                    if ui.button("Add child").clicked() {
+                        ret = add_child(path, p).map(|(v, _)| v);
+                        ui.close();
+                    }
+                    if table_view::is_table(pv(path, p)) && ui.button("Open as Table…").clicked()
+                    {
+                        table_view::open(ui.ctx(), nesting, path);
+                        ui.close();
+                    }
+                    display_key::menu(ui, scope, path, pv(path, p).as_array().unwrap());
+                }
+                ValueType::Data => {
+                    if ui.button("Edit in Hex Editor…").clicked() {
+                        hex_editor::open(ui.ctx(), nesting, path);
+                        ui.close();
+                    }
+                    if ui.button("Load Data from File…").clicked() {
+                        ui.close();
+                        ret = data_file::load(ui.ctx(), path);
+                    }
+                    if ImageKind::detect(pv(path, p).as_data().unwrap()).is_some()
+                        && ui.button("Replace Image from File…").clicked()
+                    {
+                        ui.close();
+                        ret = data_file::load_image(ui.ctx(), path);
+                    }
+                    if ui.button("Save Data to File…").clicked() {
+                        ui.close();
+                        let key = path.last().map_or("Root", String::as_str);
+                        data_file::save(ui.ctx(), key, pv(path, p).as_data().unwrap());
+                    }
                 }
                 _ => {}
             }
 
-            let Some(k) = path.last() else {
-                return;
-            };
-
-            if ui.button("Duplicate").clicked() {
-                match pv_mut(&path[..path.len() - 1], p) {
-                    Value::Dictionary(v) => {
-                        v.insert(Self::get_new_key(v.keys(), k), v.get(k).unwrap().clone());
+            if ty.is_expandable() {
+                ui.separator();
+                if ui
+                    .add(
+                        Button::new("Expand All")
+                            .shortcut_text(ui.ctx().format_shortcut(&EXPAND_SHORTCUT)),
+                    )
+                    .clicked()
+                {
+                    expand_to_depth(ui.ctx(), scope, path, p, None);
+                    ui.close();
+                }
+                if ui
+                    .add(
+                        Button::new("Collapse All")
+                            .shortcut_text(ui.ctx().format_shortcut(&COLLAPSE_SHORTCUT)),
+                    )
+                    .clicked()
+                {
+                    expand_to_depth(ui.ctx(), scope, path, p, Some(0));
+                    ui.close();
+                }
+                ui.menu_button("Expand to Depth", |ui| {
+                    for depth in 1..=MAX_EXPAND_DEPTH {
+                        if ui.button(depth.to_string()).clicked() {
+                            expand_to_depth(ui.ctx(), scope, path, p, Some(depth));
+                            ui.close();
+                        }
                     }
-                    Value::Array(v) => {
-                        v.push(v.get(k.parse::<usize>().unwrap()).unwrap().clone());
+                });
+            }
+
+            ui.separator();
+            if ui
+                .add(
+                    Button::new("Copy")
+                        .shortcut_text(ui.ctx().format_shortcut(&clipboard::COPY_SHORTCUT)),
+                )
+                .clicked()
+            {
+                clipboard::copy(ui.ctx(), &[path.to_vec()], p, false);
+                ui.close();
+            }
+            if !path.is_empty() {
+                ui.menu_button("Copy Key Path", |ui| {
+                    for format in KeyPathFormat::ALL {
+                        let text = format.format(path, p);
+                        if ui.button(format.label()).on_hover_text(&text).clicked() {
+                            ui.ctx().copy_text(text);
+                            ui.close();
+                        }
                     }
-                    _ => unreachable!(),
+                });
+            }
+            if !path.is_empty()
+                && ui
+                    .add(
+                        Button::new("Cut")
+                            .shortcut_text(ui.ctx().format_shortcut(&clipboard::CUT_SHORTCUT)),
+                    )
+                    .clicked()
+            {
+                ret = clipboard::copy(ui.ctx(), &[path.to_vec()], p, true);
+                ui.close();
+            }
+            if !path.is_empty()
+                && ui
+                    .add(
+                        Button::new("Paste")
+                            .shortcut_text(ui.ctx().format_shortcut(&clipboard::PASTE_SHORTCUT)),
+                    )
+                    .clicked()
+            {
+                clipboard::request_paste(ui.ctx(), nesting, path, false);
+                ui.close();
+            }
+            if ty.is_expandable()
+                && ui
+                    .add(
+                        Button::new("Paste as Child").shortcut_text(
+                            ui.ctx().format_shortcut(&clipboard::PASTE_CHILD_SHORTCUT),
+                        ),
+                    )
+                    .clicked()
+            {
+                clipboard::request_paste(ui.ctx(), nesting, path, true);
+                ui.close();
+            }
+
+            if nesting.is_empty() && changes::mark(ui.ctx(), path).is_some_and(|v| v.revertible) {
+                ui.separator();
+                if ui.button("Revert This Node").clicked() {
+                    ret = changes::revert(ui.ctx(), path, p);
+                    ui.close();
                 }
+            }
+
+            if path.is_empty() {
+                return;
+            }
+            ui.separator();
+            if ui.button("Duplicate").clicked() {
+                ret = duplicate(path, p);
                 ui.close();
-                ret |= ChangeState::Changed;
             }
 
             if ui.button("Remove").clicked() {
-                match pv_mut(&path[..path.len() - 1], p) {
-                    Value::Dictionary(v) => {
-                        v.remove(k);
-                    }
-                    Value::Array(v) => {
-                        v.remove(k.parse::<usize>().unwrap());
-                    }
-                    _ => unreachable!(),
-                }
+                ret = Some(Command::Remove {
+                    path: path.to_vec(),
+                });
                 ui.close();
-                ret |= ChangeState::Removed;
             }
         });
 
@@ -149,165 +451,229 @@ impl PlistEntry {
     fn show_immutable_key(
         ui: &mut egui::Ui,
         mut s: &str,
+        nesting: &[Vec<String>],
+        scope: Option<Id>,
         path: &[String],
-        p: &mut Value,
-    ) -> ChangeState {
+        p: &Value,
+        selection: &mut Selection,
+    ) -> (Response, Option<Command>) {
         let resp = ui.add(
             TextEdit::singleline(&mut s)
                 .desired_width(f32::INFINITY)
                 .frame(egui::Frame::NONE),
         );
-        Self::render_menu(&resp, path, p)
+        let ret = Self::render_menu(&resp, nesting, scope, path, p, selection);
+        (resp, ret)
     }
 
-    pub fn show(self, body: &mut TableBody) -> ChangeState {
-        let Self { data, mut path, id } = self;
-        let mut state = State::load(body.ui_mut().ctx(), id).unwrap_or_default();
-        let mut ty = ValueType::from_val(&path, &data.lock().unwrap());
-        let keys = if ty.is_expandable() {
-            child_keys(&path, &data.lock().unwrap())
-        } else {
-            Vec::new()
-        };
-        let mut ret = ChangeState::Unchanged;
-        body.row(20.0, |mut row| {
-            let resp = row
-                .col(|ui| {
-                    let prev_item_spacing = ui.spacing().item_spacing;
-                    ui.spacing_mut().item_spacing.x = 0.0;
-                    ui.add_space(ui.spacing().indent * path.len() as f32);
-                    if ty.is_expandable() {
-                        let size = vec2(ui.spacing().indent, ui.spacing().icon_width);
-                        let (_id, rect) = ui.allocate_space(size);
-                        let response = ui.interact(rect, self.id, Sense::click());
-                        if response.clicked() {
-                            state.expanded = !state.expanded;
-                            ui.ctx().request_repaint();
-                        }
+    /// Outlines the cell the keyboard is on.
+    fn show_cursor(ui: &egui::Ui, selection: &Selection, path: &[String], column: Column) {
+        if selection.is_cursor(path, column) {
+            ui.painter().rect_stroke(
+                ui.max_rect(),
+                2.0,
+                ui.visuals().selection.stroke,
+                egui::StrokeKind::Inside,
+            );
+        }
+    }
+
+    /// Clears an edit request the cell had nothing to edit for, and follows Tab out of an edit
+    /// to the next cell.
+    fn end_edit(ctx: &Context, selection: &mut Selection, column: Column) {
+        click_text_edit::cancel_edit_request(ctx);
+        if let Some(backwards) = click_text_edit::take_tab(ctx) {
+            selection.request_edit(column.next(backwards));
+        }
+    }
 
-                        let (mut icon_rect, _) = ui.spacing().icon_rectangles(response.rect);
-                        icon_rect.set_center(pos2(
-                            response.rect.left() + ui.spacing().indent / 2.0,
-                            response.rect.center().y,
-                        ));
-                        let small_icon_response = response.with_new_rect(icon_rect);
-                        egui::collapsing_header::paint_default_icon(
-                            ui,
-                            state.openness(id, ui.ctx()),
-                            &small_icon_response,
-                        );
+    /// Lays out the row, leaving it empty if its path no longer holds, and returns the edits
+    /// made in it.
+    pub fn show(self, row: &mut TableRow) -> Vec<Command> {
+        let Self {
+            data,
+            path,
+            id,
+            scope,
+            nesting,
+            indent,
+            filtering,
+            selection,
+        } = self;
+        let mut ret = Vec::new();
+        let Some(value) = try_pv(&path, data) else {
+            return ret;
+        };
+        let ty = ValueType::from_val(&[], value);
+        let len = match value {
+            Value::Dictionary(v) => v.len(),
+            Value::Array(v) => v.len(),
+            _ => 0,
+        };
+        let nested = value.as_data().and_then(Format::detect);
+        let expandable = ty.is_expandable() || nested.is_some();
+        let edit = selection.take_edit(&path);
+        row.set_selected(selection.contains(&path));
+        let mut key_resp = None;
+        let resp = row
+            .col(|ui| {
+                if nesting.is_empty() {
+                    changes::gutter(ui, &path);
+                }
+                Self::show_cursor(ui, selection, &path, Column::Key);
+                let prev_item_spacing = ui.spacing().item_spacing;
+                ui.spacing_mut().item_spacing.x = 0.0;
+                ui.add_space(ui.spacing().indent * indent as f32);
+                if expandable {
+                    let mut state = State::load(ui.ctx(), id).unwrap_or_default();
+                    let size = vec2(ui.spacing().indent, ui.spacing().icon_width);
+                    let (_id, rect) = ui.allocate_space(size);
+                    let mut response = ui.interact(rect, self.id, Sense::click());
+                    if let Some(format) = nested {
+                        response = response.on_hover_text(format!("Contains a {}", format.label()));
                     }
-                    let mut data = data.lock().unwrap();
-                    if path.is_empty() {
-                        ret |= Self::show_immutable_key(ui, "Root", &path, &mut data);
-                        return;
+                    if response.clicked() {
+                        state.expanded = !state.expanded;
+                        state.clone().store(ui.ctx(), id);
+                        tree::invalidate(ui.ctx());
+                        ui.ctx().request_repaint();
                     }
-                    let name = path.last().unwrap().clone();
-                    let Some(dict) = pv_mut(&path[..path.len() - 1], &mut data).as_dictionary_mut()
-                    else {
-                        ret |= Self::show_immutable_key(ui, name.as_str(), &path, &mut data);
-                        return;
-                    };
-                    let dict_clone = dict.clone();
-                    let resp = ui.add(ClickableTextEdit::from_get_set(
-                        |v| {
-                            v.map_or_else(
-                                || name.clone(),
-                                |val| {
-                                    if !dict.contains_key(&val) {
-                                        dict.insert(val.clone(), dict.get(&name).unwrap().clone());
-                                        path.last_mut().unwrap().clone_from(&val);
-                                        dict.remove(&name);
-                                    }
-                                    val
-                                },
-                            )
-                        },
-                        |v| name == v || !dict_clone.contains_key(v),
-                        false,
+
+                    let (mut icon_rect, _) = ui.spacing().icon_rectangles(response.rect);
+                    icon_rect.set_center(pos2(
+                        response.rect.left() + ui.spacing().indent / 2.0,
+                        response.rect.center().y,
                     ));
-                    ui.spacing_mut().item_spacing = prev_item_spacing;
-                    ret |= Self::render_menu(&resp, &path, &mut data);
-                })
-                .1;
-            if ret == ChangeState::Removed {
-                return;
-            }
-            ret |= Self::render_menu(&resp, &path, &mut data.lock().unwrap());
-            row.col(|ui| {
-                let prev_type = ty;
-                ComboBox::from_id_salt(id.with("type"))
-                    .selected_text(format!("{ty:?}"))
-                    .show_ui(ui, |ui| {
-                        ui.selectable_value(&mut ty, ValueType::Array, "Array");
-                        ui.selectable_value(&mut ty, ValueType::Dictionary, "Dictionary");
-                        if !path.is_empty() {
-                            ui.selectable_value(&mut ty, ValueType::Boolean, "Boolean");
-                            ui.selectable_value(&mut ty, ValueType::Data, "Data");
-                            ui.selectable_value(&mut ty, ValueType::Real, "Real");
-                            ui.selectable_value(&mut ty, ValueType::Integer, "Integer");
-                            ui.selectable_value(&mut ty, ValueType::String, "String");
-                        }
-                    });
-                if prev_type != ty {
-                    let mut data = data.lock().unwrap();
-                    *pv_mut(&path, &mut data) = match ty {
-                        ValueType::Array => Value::Array(Default::default()),
-                        ValueType::Dictionary => Value::Dictionary(Default::default()),
-                        ValueType::Boolean => Value::Boolean(Default::default()),
-                        ValueType::Data => Value::Data(Default::default()),
-                        ValueType::Date => Value::Date(SystemTime::now().into()),
-                        ValueType::Real => Value::Real(Default::default()),
-                        ValueType::Integer => Value::Integer(0.into()),
-                        ValueType::String => Value::String(Default::default()),
-                    };
-                    ret |= if prev_type.is_expandable() || ty.is_expandable() {
-                        ChangeState::Removed
-                    } else {
-                        ChangeState::Changed
-                    };
+                    let small_icon_response = response.with_new_rect(icon_rect);
+                    egui::collapsing_header::paint_default_icon(
+                        ui,
+                        if filtering {
+                            1.0
+                        } else {
+                            state.openness(id, ui.ctx())
+                        },
+                        &small_icon_response,
+                    );
                 }
-            });
-            if ret == ChangeState::Removed {
-                return;
-            }
-            row.col(|ui| {
-                if ty.is_expandable() {
-                    let len = keys.len();
-                    let s = if len == 1 { "" } else { "s" };
-                    match ty {
-                        ValueType::Array => {
-                            ui.add_enabled(false, Label::new(format!("{len} ordered object{s}")));
-                        }
-                        ValueType::Dictionary => {
-                            ui.add_enabled(false, Label::new(format!("{len} key/value pair{s}")));
-                        }
-                        _ => unreachable!(),
-                    }
+                let Some((name, parent)) = path.split_last() else {
+                    let (resp, command) = Self::show_immutable_key(
+                        ui, "Root", nesting, scope, &path, data, selection,
+                    );
+                    key_resp = Some(resp);
+                    ret.extend(command);
                     return;
+                };
+                let Some(dict) = pv(parent, data).as_dictionary() else {
+                    let label = display_key::label(ui.ctx(), scope, &path, data);
+                    let name = label.map_or_else(|| name.clone(), |v| format!("{name} · {v}"));
+                    let (resp, command) = Self::show_immutable_key(
+                        ui,
+                        name.as_str(),
+                        nesting,
+                        scope,
+                        &path,
+                        data,
+                        selection,
+                    );
+                    key_resp = Some(resp);
+                    ret.extend(command);
+                    return;
+                };
+                if edit == Some(Column::Key) {
+                    click_text_edit::request_edit(ui.ctx());
                 }
-
-                if PlistValue::new(&path, data.clone()).show(ui) {
-                    ret |= ChangeState::Changed;
-                }
-            });
-        });
-        if ret == ChangeState::Removed {
-            return ret;
+                let resp = ui.add(ClickableTextEdit::from_get_set(
+                    |v| {
+                        v.map_or_else(
+                            || name.clone(),
+                            |key| {
+                                if !dict.contains_key(&key) {
+                                    ret.push(Command::Rename {
+                                        path: path.clone(),
+                                        key: key.clone(),
+                                    });
+                                }
+                                key
+                            },
+                        )
+                    },
+                    |v| name == v || !dict.contains_key(v),
+                    false,
+                ));
+                ui.spacing_mut().item_spacing = prev_item_spacing;
+                ret.extend(Self::render_menu(
+                    &resp, nesting, scope, &path, data, selection,
+                ));
+                key_resp = Some(resp);
+            })
+            .1;
+        Self::end_edit(&resp.ctx, selection, Column::Key);
+        let modifiers = row.response().ctx.input(|i| i.modifiers);
+        if let Some(key_resp) = key_resp
+            && key_resp.clicked()
+        {
+            selection.click(path.clone(), modifiers);
+            if !key_resp.double_clicked() {
+                key_resp.surrender_focus();
+            }
+        } else if resp.clicked() {
+            selection.click(path.clone(), modifiers);
         }
-        if state.expanded {
-            for k in keys {
-                ret |= Self::new(
-                    data.clone(),
-                    path.iter().chain(std::iter::once(&k)).cloned().collect(),
-                )
-                .show(body);
-                if ret == ChangeState::Removed {
-                    break;
+        ret.extend(Self::render_menu(
+            &resp, nesting, scope, &path, data, selection,
+        ));
+        row.col(|ui| {
+            Self::show_cursor(ui, selection, &path, Column::Type);
+            if edit == Some(Column::Type) {
+                let button_id = ui.make_persistent_id(id.with("type"));
+                egui::Popup::open_id(ui.ctx(), button_id.with("popup"));
+            }
+            let mut new_ty = ty;
+            ComboBox::from_id_salt(id.with("type"))
+                .selected_text(format!("{ty:?}"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut new_ty, ValueType::Array, "Array");
+                    ui.selectable_value(&mut new_ty, ValueType::Dictionary, "Dictionary");
+                    if !path.is_empty() {
+                        ui.selectable_value(&mut new_ty, ValueType::Boolean, "Boolean");
+                        ui.selectable_value(&mut new_ty, ValueType::Data, "Data");
+                        ui.selectable_value(&mut new_ty, ValueType::Date, "Date");
+                        ui.selectable_value(&mut new_ty, ValueType::Real, "Real");
+                        ui.selectable_value(&mut new_ty, ValueType::Integer, "Integer");
+                        ui.selectable_value(&mut new_ty, ValueType::String, "String");
+                    }
+                });
+            if new_ty != ty {
+                let key = path.last().map_or("New Child", String::as_str);
+                let pending = PendingConversion {
+                    to: Some(new_ty),
+                    targets: vec![(path.clone(), conversions(value, key, new_ty))],
+                };
+                ret.extend(Self::change_type(ui.ctx(), nesting, pending));
+            }
+        });
+        row.col(|ui| {
+            Self::show_cursor(ui, selection, &path, Column::Value);
+            if ty.is_expandable() {
+                let s = if len == 1 { "" } else { "s" };
+                match ty {
+                    ValueType::Array => {
+                        ui.add_enabled(false, Label::new(format!("{len} ordered object{s}")));
+                    }
+                    ValueType::Dictionary => {
+                        ui.add_enabled(false, Label::new(format!("{len} key/value pair{s}")));
+                    }
+                    _ => unreachable!(),
                 }
+                return;
             }
-        }
-        state.store(body.ui_mut().ctx(), id);
+
+            if edit == Some(Column::Value) {
+                click_text_edit::request_edit(ui.ctx());
+            }
+            ret.extend(PlistValue::new(&path, data, nesting).show(ui));
+            Self::end_edit(ui.ctx(), selection, Column::Value);
+        });
         ret
     }
 }
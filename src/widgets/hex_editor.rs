@@ -0,0 +1,681 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::ops::Range;
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use egui::{
+    Context, Event, EventFilter, Id, Key, Label, Response, RichText, ScrollArea, Sense, TextStyle,
+    Ui,
+};
+use plist::Value;
+
+use super::{click_text_edit::ClickableTextEdit, nested};
+use crate::{
+    document::Command,
+    utils::{int_to_i128, parse_integer, try_pv},
+};
+
+const BYTES_PER_ROW: usize = 16;
+/// Longest selection shown as text in the side panel.
+const MAX_TEXT_LEN: usize = 4096;
+
+/// A way of reading the selected bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Interpretation {
+    Int {
+        width: usize,
+        signed: bool,
+        big_endian: bool,
+    },
+    Uuid,
+    Utf8,
+    Utf16,
+    Base64,
+}
+
+impl Interpretation {
+    fn all() -> Vec<Self> {
+        let mut ret = Vec::new();
+        for width in [1, 2, 4, 8] {
+            for signed in [false, true] {
+                for big_endian in [false, true] {
+                    if width > 1 || !big_endian {
+                        ret.push(Self::Int {
+                            width,
+                            signed,
+                            big_endian,
+                        });
+                    }
+                }
+            }
+        }
+        ret.extend([Self::Uuid, Self::Utf8, Self::Utf16, Self::Base64]);
+        ret
+    }
+
+    fn label(self) -> String {
+        match self {
+            Self::Int {
+                width,
+                signed,
+                big_endian,
+            } => {
+                let sign = if signed { 'i' } else { 'u' };
+                let bits = width * 8;
+                match (width, big_endian) {
+                    (1, _) => format!("{sign}{bits}"),
+                    (_, false) => format!("{sign}{bits} LE"),
+                    (_, true) => format!("{sign}{bits} BE"),
+                }
+            }
+            Self::Uuid => "UUID".into(),
+            Self::Utf8 => "UTF-8".into(),
+            Self::Utf16 => "UTF-16 LE".into(),
+            Self::Base64 => "Base64".into(),
+        }
+    }
+
+    /// The bytes read: a fixed width from the start of the selection, or all of it.
+    fn range(self, selection: Range<usize>, len: usize) -> Option<Range<usize>> {
+        let width = match self {
+            Self::Int { width, .. } => width,
+            Self::Uuid => 16,
+            _ => return (selection.len() <= MAX_TEXT_LEN).then_some(selection),
+        };
+        let end = selection.start + width;
+        (end <= len).then_some(selection.start..end)
+    }
+
+    fn decode(self, bytes: &[u8]) -> Option<String> {
+        match self {
+            Self::Int {
+                width,
+                signed,
+                big_endian,
+            } => {
+                let mut buf = [0; 8];
+                let v = if big_endian {
+                    buf[8 - width..].copy_from_slice(bytes);
+                    u64::from_be_bytes(buf)
+                } else {
+                    buf[..width].copy_from_slice(bytes);
+                    u64::from_le_bytes(buf)
+                };
+                Some(if signed {
+                    let shift = 64 - width * 8;
+                    (((v << shift) as i64) >> shift).to_string()
+                } else {
+                    v.to_string()
+                })
+            }
+            Self::Uuid => {
+                let v = hex::encode_upper(bytes);
+                Some(format!(
+                    "{}-{}-{}-{}-{}",
+                    &v[..8],
+                    &v[8..12],
+                    &v[12..16],
+                    &v[16..20],
+                    &v[20..]
+                ))
+            }
+            Self::Utf8 => std::str::from_utf8(bytes).ok().map(str::to_owned),
+            Self::Utf16 => {
+                if !bytes.len().is_multiple_of(2) {
+                    return None;
+                }
+                let units = bytes
+                    .chunks_exact(2)
+                    .map(|v| u16::from_le_bytes([v[0], v[1]]))
+                    .collect::<Vec<_>>();
+                String::from_utf16(&units).ok()
+            }
+            Self::Base64 => Some(STANDARD.encode(bytes)),
+        }
+    }
+
+    fn encode(self, s: &str) -> Option<Vec<u8>> {
+        match self {
+            Self::Int {
+                width,
+                signed,
+                big_endian,
+            } => {
+                let v = int_to_i128(parse_integer(s)?);
+                let bits = width * 8;
+                let range = if signed {
+                    -(1i128 << (bits - 1))..=(1i128 << (bits - 1)) - 1
+                } else {
+                    0..=(1i128 << bits) - 1
+                };
+                if !range.contains(&v) {
+                    return None;
+                }
+                let v = v as u64;
+                Some(if big_endian {
+                    v.to_be_bytes()[8 - width..].to_vec()
+                } else {
+                    v.to_le_bytes()[..width].to_vec()
+                })
+            }
+            Self::Uuid => {
+                let v = hex::decode(s.trim().trim_matches(['{', '}']).replace('-', "")).ok()?;
+                (v.len() == 16).then_some(v)
+            }
+            Self::Utf8 => Some(s.as_bytes().to_vec()),
+            Self::Utf16 => Some(s.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+            Self::Base64 => STANDARD.decode(s.trim()).ok(),
+        }
+    }
+}
+
+/// The open data editor. The cursor and anchor are byte offsets; the selection spans both
+/// inclusively, and a cursor at the end of the data selects nothing so typing appends.
+#[derive(Clone, Debug)]
+struct HexEditor {
+    /// The Data values leading to the document, if it's a nested one.
+    nesting: Vec<Vec<String>>,
+    path: Vec<String>,
+    cursor: usize,
+    anchor: usize,
+    low_nibble: bool,
+    insert: bool,
+    dragging: bool,
+}
+
+fn editor_id() -> Id {
+    Id::new("HexEditor")
+}
+
+/// Opens the data editor on the node at `path` in the document `nesting` leads to, replacing any
+/// already open.
+pub fn open(ctx: &Context, nesting: &[Vec<String>], path: &[String]) {
+    ctx.data_mut(|d| {
+        d.insert_temp(
+            editor_id(),
+            HexEditor {
+                nesting: nesting.to_vec(),
+                path: path.to_vec(),
+                cursor: 0,
+                anchor: 0,
+                low_nibble: false,
+                insert: false,
+                dragging: false,
+            },
+        );
+    });
+}
+
+/// Shows the data editor if one is open, returning the edit made to the data.
+pub fn show(ctx: &Context, root: &Value) -> Option<Command> {
+    let mut editor = ctx.data_mut(|d| d.get_temp::<HexEditor>(editor_id()))?;
+    let Some(Some(mut data)) = nested::read(ctx, root, &editor.nesting, |p| {
+        try_pv(&editor.path, p)
+            .and_then(Value::as_data)
+            .map(<[u8]>::to_vec)
+    }) else {
+        ctx.data_mut(|d| d.remove::<HexEditor>(editor_id()));
+        return None;
+    };
+
+    let title = if editor.path.is_empty() {
+        "Root".to_owned()
+    } else {
+        editor.path.join(" › ")
+    };
+    let mut open = true;
+    let mut changed = false;
+    egui::Window::new(format!("Data Editor — {title}"))
+        .id(editor_id())
+        .open(&mut open)
+        .resizable(false)
+        .show(ctx, |ui| changed = editor.ui(ui, &mut data));
+
+    let command = Command::Set {
+        path: editor.path.clone(),
+        value: Value::Data(data),
+    };
+    let command = changed.then(|| Command::nested(&editor.nesting, command));
+    if open {
+        ctx.data_mut(|d| d.insert_temp(editor_id(), editor));
+    } else {
+        ctx.data_mut(|d| d.remove::<HexEditor>(editor_id()));
+    }
+    command
+}
+
+impl HexEditor {
+    fn selection(&self, len: usize) -> Range<usize> {
+        let end = (self.cursor.max(self.anchor) + 1).min(len);
+        self.cursor.min(self.anchor).min(end)..end
+    }
+
+    const fn move_to(&mut self, i: usize, extend: bool) {
+        self.cursor = i;
+        if !extend {
+            self.anchor = i;
+        }
+        self.low_nibble = false;
+    }
+
+    fn type_nibble(&mut self, data: &mut Vec<u8>, nibble: u8) {
+        if self.low_nibble
+            && let Some(b) = data.get_mut(self.cursor)
+        {
+            *b = (*b & 0xF0) | nibble;
+            self.move_to(self.cursor + 1, false);
+            return;
+        }
+        let selection = self.selection(data.len());
+        if self.insert && selection.len() > 1 {
+            data.drain(selection.clone());
+        }
+        self.move_to(selection.start, false);
+        if self.insert || self.cursor >= data.len() {
+            data.insert(self.cursor, nibble << 4);
+        } else {
+            data[self.cursor] = (data[self.cursor] & 0x0F) | (nibble << 4);
+        }
+        self.low_nibble = true;
+    }
+
+    /// Deletes the selection, or the byte before the cursor for Backspace.
+    fn delete(&mut self, data: &mut Vec<u8>, backward: bool) -> bool {
+        let selection = self.selection(data.len());
+        let range = if backward && selection.len() <= 1 {
+            if self.cursor == 0 {
+                return false;
+            }
+            self.cursor - 1..self.cursor
+        } else {
+            selection
+        };
+        if range.is_empty() {
+            return false;
+        }
+        data.drain(range.clone());
+        self.move_to(range.start, false);
+        true
+    }
+
+    /// Replaces `range` with `bytes` and selects what was put in.
+    fn replace(&mut self, data: &mut Vec<u8>, range: Range<usize>, bytes: Vec<u8>) {
+        let len = bytes.len();
+        data.splice(range.clone(), bytes);
+        self.move_to(range.start, false);
+        if len > 0 {
+            self.cursor = range.start + len - 1;
+        }
+    }
+
+    fn ui(&mut self, ui: &mut Ui, data: &mut Vec<u8>) -> bool {
+        self.cursor = self.cursor.min(data.len());
+        self.anchor = self.anchor.min(data.len());
+
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.insert, false, "Overwrite");
+            ui.selectable_value(&mut self.insert, true, "Insert")
+                .on_hover_text("Toggle with the Insert key");
+            ui.separator();
+            ui.label(format!("{} bytes", data.len()));
+            let selection = self.selection(data.len());
+            if !selection.is_empty() {
+                ui.separator();
+                ui.label(format!(
+                    "Selected 0x{:X}–0x{:X} ({} bytes)",
+                    selection.start,
+                    selection.end - 1,
+                    selection.len()
+                ));
+            }
+        });
+        ui.separator();
+
+        ui.horizontal_top(|ui| {
+            let mut changed = self.dump(ui, data);
+            ui.separator();
+            changed |= self.interpretations(ui, data);
+            changed
+        })
+        .inner
+    }
+
+    fn handle_input(&mut self, ui: &Ui, data: &mut Vec<u8>) -> bool {
+        let events = ui.input(|i| i.events.clone());
+        let mut changed = false;
+        for event in events {
+            match event {
+                Event::Text(text) => {
+                    for nibble in text.chars().filter_map(|c| c.to_digit(16)) {
+                        self.type_nibble(data, nibble as u8);
+                        changed = true;
+                    }
+                }
+                Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } => {
+                    let len = data.len();
+                    let extend = modifiers.shift;
+                    match key {
+                        Key::ArrowLeft => self.move_to(self.cursor.saturating_sub(1), extend),
+                        Key::ArrowRight => self.move_to((self.cursor + 1).min(len), extend),
+                        Key::ArrowUp => {
+                            self.move_to(self.cursor.saturating_sub(BYTES_PER_ROW), extend);
+                        }
+                        Key::ArrowDown => {
+                            self.move_to((self.cursor + BYTES_PER_ROW).min(len), extend);
+                        }
+                        Key::Backspace => changed |= self.delete(data, true),
+                        Key::Delete => changed |= self.delete(data, false),
+                        Key::Insert => self.insert = !self.insert,
+                        _ => {}
+                    }
+                }
+                Event::Copy | Event::Cut => {
+                    let selection = self.selection(data.len());
+                    if !selection.is_empty() {
+                        ui.ctx().copy_text(hex::encode_upper(&data[selection]));
+                        if matches!(event, Event::Cut) {
+                            changed |= self.delete(data, false);
+                        }
+                    }
+                }
+                Event::Paste(text) => {
+                    if let Ok(bytes) = hex::decode(text.split_whitespace().collect::<String>()) {
+                        self.replace(data, self.selection(data.len()), bytes);
+                        changed = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+        changed
+    }
+
+    fn cell(&mut self, ui: &mut Ui, i: usize, text: String, selection: &Range<usize>) -> Response {
+        let mut text = RichText::new(text).monospace();
+        if selection.contains(&i) {
+            text = text
+                .background_color(ui.visuals().selection.bg_fill)
+                .color(ui.visuals().selection.stroke.color);
+        }
+        if i == self.cursor {
+            text = text.underline();
+        }
+        let response = ui.add(
+            Label::new(text)
+                .sense(Sense::click_and_drag())
+                .selectable(false),
+        );
+        if response.drag_started() || response.clicked() {
+            self.move_to(i, ui.input(|v| v.modifiers.shift));
+            self.dragging = true;
+        } else if self.dragging && response.contains_pointer() {
+            self.move_to(i, true);
+        }
+        response
+    }
+
+    fn dump(&mut self, ui: &mut Ui, data: &mut Vec<u8>) -> bool {
+        let id = editor_id().with("dump");
+        let changed = if ui.memory(|v| v.has_focus(id)) {
+            ui.memory_mut(|v| {
+                v.set_focus_lock_filter(
+                    id,
+                    EventFilter {
+                        horizontal_arrows: true,
+                        vertical_arrows: true,
+                        ..Default::default()
+                    },
+                );
+            });
+            self.handle_input(ui, data)
+        } else {
+            false
+        };
+        if !ui.input(|v| v.pointer.primary_down()) {
+            self.dragging = false;
+        }
+
+        let selection = self.selection(data.len());
+        let row_height = ui.text_style_height(&TextStyle::Monospace);
+        // One more row than needed once a row fills up, so the end of the data can be clicked.
+        let rows = data.len() / BYTES_PER_ROW + 1;
+        let mut clicked = false;
+        let output =
+            ScrollArea::vertical()
+                .max_height(400.0)
+                .show_rows(ui, row_height, rows, |ui, rows| {
+                    for row in rows {
+                        let bytes = row * BYTES_PER_ROW..(row + 1) * BYTES_PER_ROW;
+                        ui.horizontal(|ui| {
+                            ui.spacing_mut().item_spacing.x = 4.0;
+                            ui.label(
+                                RichText::new(format!("{:08X}", bytes.start))
+                                    .monospace()
+                                    .weak(),
+                            );
+                            ui.add_space(8.0);
+                            for i in bytes.clone() {
+                                if i % BYTES_PER_ROW == BYTES_PER_ROW / 2 {
+                                    ui.add_space(4.0);
+                                }
+                                let text = data
+                                    .get(i)
+                                    .map_or_else(|| "  ".into(), |b| format!("{b:02X}"));
+                                if i <= data.len() {
+                                    clicked |= self.cell(ui, i, text, &selection).clicked();
+                                } else {
+                                    ui.label(RichText::new(text).monospace());
+                                }
+                            }
+                            ui.add_space(8.0);
+                            ui.spacing_mut().item_spacing.x = 0.0;
+                            for (i, &b) in data.iter().enumerate().take(bytes.end).skip(bytes.start)
+                            {
+                                let c = if b.is_ascii_graphic() || b == b' ' {
+                                    b as char
+                                } else {
+                                    '.'
+                                };
+                                clicked |= self.cell(ui, i, c.to_string(), &selection).clicked();
+                            }
+                        });
+                    }
+                });
+
+        let response = ui.interact(output.inner_rect, id, Sense::focusable_noninteractive());
+        if clicked || self.dragging {
+            response.request_focus();
+        }
+        changed
+    }
+
+    fn interpretations(&mut self, ui: &mut Ui, data: &mut Vec<u8>) -> bool {
+        let selection = self.selection(data.len());
+        let mut edit = None;
+        ui.vertical(|ui| {
+            ui.strong("Selection as");
+            egui::Grid::new(editor_id().with("interpretations"))
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for interpretation in Interpretation::all() {
+                        let Some(range) = interpretation.range(selection.clone(), data.len())
+                        else {
+                            continue;
+                        };
+                        let Some(text) = interpretation.decode(&data[range.clone()]) else {
+                            continue;
+                        };
+                        let label = interpretation.label();
+                        ui.label(&label);
+                        ui.push_id(label, |ui| {
+                            ui.add(ClickableTextEdit::from_get_set(
+                                |v| {
+                                    if let Some(v) = v
+                                        && let Some(bytes) = interpretation.encode(&v)
+                                    {
+                                        edit = Some((range.clone(), bytes));
+                                    }
+                                    text.clone()
+                                },
+                                |v| interpretation.encode(v).is_some(),
+                                false,
+                            ));
+                        });
+                        ui.end_row();
+                    }
+                });
+        });
+        if let Some((range, bytes)) = edit {
+            self.replace(data, range, bytes);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HexEditor, Interpretation};
+
+    fn editor_at(cursor: usize, insert: bool) -> HexEditor {
+        HexEditor {
+            nesting: Vec::new(),
+            path: Vec::new(),
+            cursor,
+            anchor: cursor,
+            low_nibble: false,
+            insert,
+            dragging: false,
+        }
+    }
+
+    #[test]
+    fn integers() {
+        for v in Interpretation::all() {
+            let Interpretation::Int { width, signed, .. } = v else {
+                continue;
+            };
+            let bits = width * 8;
+            let (min, max) = if signed {
+                (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+            } else {
+                (0, (1i128 << bits) - 1)
+            };
+            for n in [min, min + 1, 0, 1, max - 1, max] {
+                let bytes = v.encode(&n.to_string()).unwrap();
+                assert_eq!(bytes.len(), width, "{}", v.label());
+                assert_eq!(v.decode(&bytes), Some(n.to_string()), "{}", v.label());
+            }
+            for n in [min - 1, max + 1] {
+                assert_eq!(v.encode(&n.to_string()), None, "{} {n}", v.label());
+            }
+            assert_eq!(v.encode("nope"), None);
+        }
+    }
+
+    #[test]
+    fn byte_order() {
+        let int = |width, signed, big_endian| Interpretation::Int {
+            width,
+            signed,
+            big_endian,
+        };
+        assert_eq!(
+            int(2, false, false).encode("0x1234"),
+            Some(vec![0x34, 0x12])
+        );
+        assert_eq!(int(2, false, true).encode("0x1234"), Some(vec![0x12, 0x34]));
+        assert_eq!(
+            int(4, true, false).encode("-2"),
+            Some(vec![0xfe, 0xff, 0xff, 0xff])
+        );
+        assert_eq!(
+            int(4, true, true).decode(&[0xff, 0xff, 0xff, 0xfe]),
+            Some("-2".into())
+        );
+        assert_eq!(int(1, true, false).decode(&[0x80]), Some("-128".into()));
+        assert_eq!(int(1, false, false).decode(&[0x80]), Some("128".into()));
+        assert_eq!(
+            int(8, false, true).decode(&[0xff; 8]),
+            Some(u64::MAX.to_string())
+        );
+    }
+
+    #[test]
+    fn text() {
+        let uuid = "01234567-89AB-CDEF-0123-456789ABCDEF";
+        let bytes = Interpretation::Uuid.encode(uuid).unwrap();
+        assert_eq!(Interpretation::Uuid.decode(&bytes), Some(uuid.into()));
+        assert_eq!(
+            Interpretation::Uuid.encode(&format!("{{{}}}", uuid.to_lowercase())),
+            Some(bytes)
+        );
+        assert_eq!(Interpretation::Uuid.encode("0123"), None);
+
+        for v in [Interpretation::Utf8, Interpretation::Utf16] {
+            let bytes = v.encode("héllo 🦀").unwrap();
+            assert_eq!(v.decode(&bytes), Some("héllo 🦀".into()), "{}", v.label());
+        }
+        assert_eq!(Interpretation::Utf16.encode("A"), Some(vec![0x41, 0]));
+        let text = Interpretation::Base64.decode(&[0, 0xff, 0x10]).unwrap();
+        assert_eq!(text, "AP8Q");
+        assert_eq!(
+            Interpretation::Base64.encode(&text),
+            Some(vec![0, 0xff, 0x10])
+        );
+        assert_eq!(Interpretation::Utf16.decode(&[0x41]), None);
+        assert_eq!(Interpretation::Utf8.decode(&[0xff]), None);
+        assert_eq!(Interpretation::Base64.encode("!!"), None);
+    }
+
+    #[test]
+    fn typing_at_end() {
+        let mut data = vec![0x12];
+        let mut editor = editor_at(1, false);
+        editor.type_nibble(&mut data, 0xA);
+        assert_eq!(data, [0x12, 0xA0]);
+        editor.type_nibble(&mut data, 0xB);
+        assert_eq!(data, [0x12, 0xAB]);
+        assert_eq!(editor.cursor, 2);
+        editor.type_nibble(&mut data, 0xC);
+        assert_eq!(data, [0x12, 0xAB, 0xC0]);
+
+        let mut data = vec![0x12, 0x34];
+        let mut editor = editor_at(0, false);
+        editor.type_nibble(&mut data, 0xF);
+        editor.type_nibble(&mut data, 0xF);
+        assert_eq!(data, [0xFF, 0x34]);
+
+        let mut editor = editor_at(1, true);
+        editor.type_nibble(&mut data, 0x5);
+        editor.type_nibble(&mut data, 0x6);
+        assert_eq!(data, [0xFF, 0x56, 0x34]);
+    }
+
+    #[test]
+    fn deleting_at_end() {
+        let mut data = vec![1, 2, 3];
+        let mut editor = editor_at(3, false);
+        assert!(!editor.delete(&mut data, false));
+        assert!(editor.delete(&mut data, true));
+        assert_eq!(data, [1, 2]);
+        assert_eq!(editor.cursor, 2);
+
+        editor.move_to(1, false);
+        assert!(editor.delete(&mut data, false));
+        assert_eq!(data, [1]);
+        assert_eq!(editor.cursor, 1);
+        assert!(editor.delete(&mut data, true));
+        assert!(data.is_empty());
+        assert!(!editor.delete(&mut data, true));
+        assert!(!editor.delete(&mut data, false));
+    }
+}
@@ -0,0 +1,135 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use egui::{ColorImage, Context, Id, Image, Response, TextureHandle, TextureOptions, Ui, vec2};
+
+use super::entry;
+
+const THUMBNAIL_SIZE: f32 = 16.0;
+const PREVIEW_SIZE: f32 = 256.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageKind {
+    Png,
+    Jpeg,
+    Tiff,
+    Icns,
+}
+
+impl ImageKind {
+    /// Recognises an image by its signature.
+    #[must_use]
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(Self::Png)
+        } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(Self::Jpeg)
+        } else if bytes.starts_with(b"II*\0") || bytes.starts_with(b"MM\0*") {
+            Some(Self::Tiff)
+        } else if bytes.starts_with(b"icns") {
+            Some(Self::Icns)
+        } else {
+            None
+        }
+    }
+
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Png => "PNG",
+            Self::Jpeg => "JPEG",
+            Self::Tiff => "TIFF",
+            Self::Icns => "ICNS",
+        }
+    }
+}
+
+/// The largest PNG in an icon family. Older entries use formats that can't be shown.
+fn icns_png(bytes: &[u8]) -> Option<&[u8]> {
+    let mut best: Option<&[u8]> = None;
+    let mut rest = bytes.get(8..)?;
+    while rest.len() >= 8 {
+        let len = u32::from_be_bytes(rest[4..8].try_into().unwrap()) as usize;
+        let Some(chunk) = rest.get(8..len.max(8)) else {
+            break;
+        };
+        if ImageKind::detect(chunk) == Some(ImageKind::Png)
+            && best.is_none_or(|v| v.len() < chunk.len())
+        {
+            best = Some(chunk);
+        }
+        rest = &rest[len.max(8)..];
+    }
+    best
+}
+
+fn decode(bytes: &[u8]) -> Option<ColorImage> {
+    let bytes = match ImageKind::detect(bytes)? {
+        ImageKind::Icns => icns_png(bytes)?,
+        _ => bytes,
+    };
+    let image = image::load_from_memory(bytes).ok()?.to_rgba8();
+    Some(ColorImage::from_rgba_unmultiplied(
+        [image.width() as usize, image.height() as usize],
+        image.as_raw(),
+    ))
+}
+
+/// The decoded image along with a hash of the bytes it came from, so it is only redone when the
+/// data changes.
+#[derive(Clone)]
+struct Cached {
+    hash: u64,
+    texture: Option<TextureHandle>,
+}
+
+fn texture(
+    ctx: &Context,
+    scope: Option<Id>,
+    path: &[String],
+    bytes: &[u8],
+) -> Option<TextureHandle> {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    let hash = hasher.finish();
+    let id = entry::row_id(scope, path).with("image");
+    if let Some(cached) = ctx.data_mut(|d| d.get_temp::<Cached>(id))
+        && cached.hash == hash
+    {
+        return cached.texture;
+    }
+    let texture =
+        decode(bytes).map(|v| ctx.load_texture(format!("{id:?}"), v, TextureOptions::LINEAR));
+    ctx.data_mut(|d| {
+        d.insert_temp(
+            id,
+            Cached {
+                hash,
+                texture: texture.clone(),
+            },
+        );
+    });
+    texture
+}
+
+/// Shows a thumbnail of the image in `bytes`, held by the row at `path` under `scope`, with a
+/// larger preview on hover. Nothing is shown unless the data is an image that can be decoded.
+pub fn thumbnail(
+    ui: &mut Ui,
+    scope: Option<Id>,
+    path: &[String],
+    bytes: &[u8],
+) -> Option<Response> {
+    let kind = ImageKind::detect(bytes)?;
+    let texture = texture(ui.ctx(), scope, path, bytes)?;
+    let [w, h] = texture.size();
+    Some(
+        ui.add(Image::new(&texture).fit_to_exact_size(vec2(THUMBNAIL_SIZE, THUMBNAIL_SIZE)))
+            .on_hover_ui(|ui| {
+                ui.add(Image::new(&texture).max_size(vec2(PREVIEW_SIZE, PREVIEW_SIZE)));
+                ui.label(format!("{} image, {w}×{h}", kind.label()));
+            }),
+    )
+}
@@ -2,6 +2,14 @@
 //! See LICENSE for details.
 
 pub mod click_text_edit;
+pub mod date;
+pub mod display_key;
 pub mod entry;
+pub mod hex_editor;
+pub mod image_preview;
+pub mod multiline;
+pub mod nested;
+pub mod table_view;
 pub mod toggle;
+pub mod tree;
 pub mod value;
@@ -0,0 +1,218 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::sync::Arc;
+
+use egui::{
+    Context, Galley, Id, Response, TextBuffer, TextEdit, TextFormat, TextStyle, Ui, Widget,
+    text::LayoutJob,
+};
+use plist::Value;
+
+use super::{entry, nested, tree};
+use crate::{document::Command, utils::try_pv};
+
+const ROW_HEIGHT: f32 = 20.0;
+/// Most lines shown by an expanded row before it scrolls.
+const MAX_ROWS: usize = 12;
+
+fn expanded_id(scope: Option<Id>, path: &[String]) -> Id {
+    entry::row_id(scope, path).with("multiline")
+}
+
+/// Whether the string row at `path` is expanded, in the document nested under `scope` if set.
+#[must_use]
+pub fn is_expanded(ctx: &Context, scope: Option<Id>, path: &[String]) -> bool {
+    ctx.data_mut(|d| {
+        d.get_persisted(expanded_id(scope, path))
+            .unwrap_or_default()
+    })
+}
+
+pub fn set_expanded(ctx: &Context, scope: Option<Id>, path: &[String], expanded: bool) {
+    ctx.data_mut(|d| d.insert_persisted(expanded_id(scope, path), expanded));
+    tree::invalidate(ctx);
+}
+
+#[must_use]
+pub fn line_count(s: &str) -> usize {
+    s.split('\n').count()
+}
+
+/// How many lines the row for `path` under `scope` shows, if it holds the expanded string `v`.
+#[must_use]
+pub fn expanded_lines(
+    ctx: &Context,
+    scope: Option<Id>,
+    path: &[String],
+    v: &Value,
+) -> Option<usize> {
+    let Value::String(s) = v else {
+        return None;
+    };
+    is_expanded(ctx, scope, path).then(|| line_count(s).clamp(2, MAX_ROWS))
+}
+
+/// Measures table rows, which are taller when showing an expanded string of so many lines.
+pub fn row_height(ui: &Ui) -> impl Fn(Option<usize>) -> f32 + use<> {
+    let text = ui.text_style_height(&TextStyle::Monospace);
+    let padding = 2.0 * ui.spacing().button_padding.y;
+    move |lines| {
+        lines.map_or(ROW_HEIGHT, |v| {
+            (v as f32).mul_add(text, padding).max(ROW_HEIGHT)
+        })
+    }
+}
+
+/// Replaces line breaks and tabs with visible marks, for showing a string on a single line.
+#[must_use]
+pub fn visible_whitespace(s: &str) -> String {
+    s.replace('\n', "↵").replace('\t', "→")
+}
+
+/// Lays text out with spaces and tabs drawn as faint marks. Each mark stands in for exactly one
+/// character, so cursor positions are unaffected.
+fn whitespace_layouter(ui: &Ui, text: &dyn TextBuffer, wrap_width: f32) -> Arc<Galley> {
+    let font = TextStyle::Monospace.resolve(ui.style());
+    let normal = TextFormat::simple(font.clone(), ui.visuals().text_color());
+    let faint = TextFormat::simple(font, ui.visuals().weak_text_color());
+    let mut job = LayoutJob::default();
+    let mut run = String::new();
+    let mut run_is_space = false;
+    for c in text.as_str().chars() {
+        let (c, is_space) = match c {
+            ' ' => ('·', true),
+            '\t' => ('→', true),
+            c => (c, false),
+        };
+        if is_space != run_is_space && !run.is_empty() {
+            let format = if run_is_space { &faint } else { &normal };
+            job.append(&std::mem::take(&mut run), 0.0, format.clone());
+        }
+        run_is_space = is_space;
+        run.push(c);
+    }
+    job.append(&run, 0.0, if run_is_space { faint } else { normal });
+    job.wrap.max_width = wrap_width;
+    ui.fonts_mut(|f| f.layout_job(job))
+}
+
+/// A multi-line text edit that shows whitespace.
+#[must_use]
+pub struct MultilineEdit<'a> {
+    text: &'a mut String,
+    rows: usize,
+    frame: bool,
+}
+
+impl<'a> MultilineEdit<'a> {
+    pub const fn new(text: &'a mut String, rows: usize, frame: bool) -> Self {
+        Self { text, rows, frame }
+    }
+}
+
+impl Widget for MultilineEdit<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let Self { text, rows, frame } = self;
+        let mut layouter = whitespace_layouter;
+        let mut textedit = TextEdit::multiline(text)
+            .desired_rows(rows)
+            .desired_width(f32::INFINITY)
+            .font(TextStyle::Monospace)
+            .lock_focus(true)
+            .layouter(&mut layouter);
+        if !frame {
+            textedit = textedit.frame(egui::Frame::NONE);
+        }
+        ui.add(textedit)
+    }
+}
+
+/// Shows an expanded string row, scrolling once it reaches the row's height.
+pub fn show_expanded(ui: &mut Ui, text: &mut String) -> Response {
+    let rows = line_count(text).clamp(2, MAX_ROWS);
+    egui::ScrollArea::vertical()
+        .id_salt("multiline")
+        .show(ui, |ui| ui.add(MultilineEdit::new(text, rows, false)))
+        .inner
+}
+
+/// The string being edited in the larger editor, kept apart until applied.
+#[derive(Clone, Debug)]
+struct Editor {
+    /// The Data values leading to the document, if it's a nested one.
+    nesting: Vec<Vec<String>>,
+    path: Vec<String>,
+    text: String,
+}
+
+fn editor_id() -> Id {
+    Id::new("MultilineEditor")
+}
+
+pub fn open(ctx: &Context, nesting: &[Vec<String>], path: &[String], text: &str) {
+    ctx.data_mut(|d| {
+        d.insert_temp(
+            editor_id(),
+            Editor {
+                nesting: nesting.to_vec(),
+                path: path.to_vec(),
+                text: text.to_owned(),
+            },
+        );
+    });
+}
+
+/// Shows the larger string editor if one is open, returning the edit once applied.
+pub fn show(ctx: &Context, root: &Value) -> Option<Command> {
+    let mut editor = ctx.data_mut(|d| d.get_temp::<Editor>(editor_id()))?;
+    let Some(Some(old)) = nested::read(ctx, root, &editor.nesting, |p| {
+        try_pv(&editor.path, p)
+            .and_then(Value::as_string)
+            .map(str::to_owned)
+    }) else {
+        ctx.data_mut(|d| d.remove::<Editor>(editor_id()));
+        return None;
+    };
+
+    let mut applied = false;
+    let mut cancelled = false;
+    egui::Modal::new(Id::new("MultilineModal")).show(ctx, |ui| {
+        ui.heading(format!("Edit {}", editor.path.join(" › ")));
+        ui.separator();
+        ui.set_width(ctx.content_rect().width() * 0.7);
+        egui::ScrollArea::vertical()
+            .max_height(ctx.content_rect().height() * 0.6)
+            .show(ui, |ui| {
+                ui.add(MultilineEdit::new(&mut editor.text, 20, true));
+            });
+        ui.separator();
+        egui::Sides::new().show(
+            ui,
+            |ui| {
+                ui.label(format!(
+                    "{} lines, {} characters",
+                    line_count(&editor.text),
+                    editor.text.chars().count()
+                ));
+            },
+            |ui| {
+                cancelled = ui.button("Cancel").clicked();
+                applied = ui.button("Apply").clicked();
+            },
+        );
+    });
+
+    if !applied && !cancelled {
+        ctx.data_mut(|d| d.insert_temp(editor_id(), editor));
+        return None;
+    }
+    ctx.data_mut(|d| d.remove::<Editor>(editor_id()));
+    (applied && old != editor.text).then(|| {
+        let command = Command::Set {
+            path: editor.path,
+            value: Value::String(editor.text),
+        };
+        Command::nested(&editor.nesting, command)
+    })
+}
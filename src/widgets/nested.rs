@@ -0,0 +1,89 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{Arc, Mutex},
+};
+
+use egui::{Context, Id};
+use plist::Value;
+
+use super::entry;
+use crate::{format::Format, selection::Selection, utils::try_pv};
+
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A nested plist, decoded once and kept until the bytes it came from change.
+///
+/// It's only ever read. Edits to it are [`Command::Nested`](crate::document::Command::Nested)s
+/// applied to the open document, which decode and encode the bytes themselves.
+#[derive(Clone)]
+pub struct Nested {
+    pub value: Arc<Value>,
+    pub selection: Arc<Mutex<Selection>>,
+    hash: u64,
+}
+
+impl Nested {
+    /// Decodes the plist in `bytes`, reusing last frame's copy if they haven't changed.
+    #[must_use]
+    pub fn load(ctx: &Context, id: Id, bytes: &[u8]) -> Option<Self> {
+        let hash = hash(bytes);
+        if let Some(nested) = ctx.data_mut(|d| d.get_temp::<Self>(id))
+            && nested.hash == hash
+        {
+            return Some(nested);
+        }
+        let value = Format::detect(bytes)?.decode(bytes)?;
+        let nested = Self {
+            value: Arc::new(value),
+            selection: Arc::default(),
+            hash,
+        };
+        ctx.data_mut(|d| d.insert_temp(id, nested.clone()));
+        Some(nested)
+    }
+}
+
+/// The id that keeps apart the rows of the document reached through each Data value in
+/// `nesting`. `None` for the open document.
+#[must_use]
+pub fn scope(nesting: &[Vec<String>]) -> Option<Id> {
+    nesting.iter().fold(None, |scope, path| {
+        Some(entry::row_id(scope, path).with("nested"))
+    })
+}
+
+/// The document reached through each Data value in `nesting`, outermost first, or `None` if one
+/// of them no longer holds a plist.
+fn load(ctx: &Context, root: &Value, nesting: &[Vec<String>]) -> Option<Nested> {
+    let mut ret: Option<Nested> = None;
+    for (i, path) in nesting.iter().enumerate() {
+        let next = {
+            let p = ret.as_ref().map_or(root, |v| &v.value);
+            let bytes = try_pv(path, p)?.as_data()?;
+            Nested::load(ctx, scope(&nesting[..=i])?, bytes)?
+        };
+        ret = Some(next);
+    }
+    ret
+}
+
+/// Calls `f` with the document reached through each Data value in `nesting`, outermost first,
+/// or with `root` itself if there are none. `None` if one of them no longer holds a plist.
+pub fn read<R>(
+    ctx: &Context,
+    root: &Value,
+    nesting: &[Vec<String>],
+    f: impl FnOnce(&Value) -> R,
+) -> Option<R> {
+    if nesting.is_empty() {
+        return Some(f(root));
+    }
+    Some(f(&load(ctx, root, nesting)?.value))
+}
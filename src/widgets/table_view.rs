@@ -0,0 +1,297 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::cmp::Ordering;
+
+use egui::{Align, Context, Id, Label, Layout, RichText, Sense, Ui};
+use egui_extras::{Column, TableBuilder};
+use plist::{Dictionary, Value};
+
+use super::{nested, value::PlistValue};
+use crate::{
+    document::Command,
+    find::value_text,
+    utils::{ValueType, int_to_i128, pv, try_pv},
+};
+
+const ROW_HEIGHT: f32 = 22.0;
+
+/// Whether the value can be shown as a table: a non-empty array holding only dictionaries.
+#[must_use]
+pub fn is_table(v: &Value) -> bool {
+    v.as_array()
+        .is_some_and(|v| !v.is_empty() && v.iter().all(|v| v.as_dictionary().is_some()))
+}
+
+/// Every key used by the items, in the order first seen, with the type it first has.
+fn columns(items: &[Value]) -> Vec<(String, ValueType)> {
+    let mut ret: Vec<(String, ValueType)> = Vec::new();
+    for (k, v) in items.iter().filter_map(Value::as_dictionary).flatten() {
+        if !ret.iter().any(|(v, _)| v == k) {
+            ret.push((k.clone(), ValueType::from_val(&[], v)));
+        }
+    }
+    ret
+}
+
+/// Orders cells for sorting: numbers by value, anything else by its text, and missing keys last.
+fn cmp_cells(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    fn number(v: &Value) -> Option<f64> {
+        match v {
+            Value::Integer(v) => Some(int_to_i128(*v) as f64),
+            Value::Real(v) => Some(*v),
+            _ => None,
+        }
+    }
+    fn text(v: &Value) -> String {
+        match v {
+            Value::Boolean(v) => v.to_string(),
+            Value::Date(v) => v.to_xml_format(),
+            v => value_text(v).unwrap_or_default().to_lowercase(),
+        }
+    }
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => match (number(a), number(b)) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            _ => text(a).cmp(&text(b)),
+        },
+    }
+}
+
+/// The open table. Sorting only changes the order rows are shown in, not the array.
+#[derive(Clone, Debug)]
+struct TableView {
+    /// The Data values leading to the document, if it's a nested one.
+    nesting: Vec<Vec<String>>,
+    path: Vec<String>,
+    /// The column sorted by, and whether descending.
+    sort: Option<(String, bool)>,
+    hidden: Vec<String>,
+}
+
+fn view_id() -> Id {
+    Id::new("TableView")
+}
+
+/// Opens the table view on the array at `path` in the document `nesting` leads to, replacing
+/// any already open.
+pub fn open(ctx: &Context, nesting: &[Vec<String>], path: &[String]) {
+    ctx.data_mut(|d| {
+        d.insert_temp(
+            view_id(),
+            TableView {
+                nesting: nesting.to_vec(),
+                path: path.to_vec(),
+                sort: None,
+                hidden: Vec::new(),
+            },
+        );
+    });
+}
+
+/// Shows the table view if one is open, returning the edits made to the array.
+pub fn show(ctx: &Context, root: &Value) -> Option<Command> {
+    let mut view = ctx.data_mut(|d| d.get_temp::<TableView>(view_id()))?;
+    let title = if view.path.is_empty() {
+        "Root".to_owned()
+    } else {
+        view.path.join(" › ")
+    };
+    let mut open = true;
+    let nesting = view.nesting.clone();
+    let Some(Some(command)) = nested::read(ctx, root, &nesting, |p| {
+        if !try_pv(&view.path, p).is_some_and(is_table) {
+            return None;
+        }
+        let mut ret = None;
+        egui::Window::new(format!("Table — {title}"))
+            .id(view_id())
+            .open(&mut open)
+            .default_size([720.0, 400.0])
+            .show(ctx, |ui| ret = view.ui(ui, p));
+        Some(ret)
+    }) else {
+        ctx.data_mut(|d| d.remove::<TableView>(view_id()));
+        return None;
+    };
+
+    if open {
+        ctx.data_mut(|d| d.insert_temp(view_id(), view));
+    } else {
+        ctx.data_mut(|d| d.remove::<TableView>(view_id()));
+    }
+    command.map(|v| Command::nested(&nesting, v))
+}
+
+impl TableView {
+    /// The columns, and the item indices in the order rows are shown.
+    fn layout(&self, doc: &Value) -> (Vec<(String, ValueType)>, Vec<usize>) {
+        let items = pv(&self.path, doc).as_array().unwrap();
+        (columns(items), self.order(items))
+    }
+
+    fn order(&self, items: &[Value]) -> Vec<usize> {
+        let mut ret = (0..items.len()).collect::<Vec<_>>();
+        if let Some((k, descending)) = &self.sort {
+            let cell = |i: usize| items[i].as_dictionary().and_then(|v| v.get(k));
+            ret.sort_by(|&a, &b| {
+                let ord = cmp_cells(cell(a), cell(b));
+                if *descending { ord.reverse() } else { ord }
+            });
+        }
+        ret
+    }
+
+    /// Cycles a column through ascending, descending and unsorted.
+    fn toggle_sort(&mut self, k: &str) {
+        self.sort = match self.sort.take() {
+            Some((v, false)) if v == k => Some((v, true)),
+            Some((v, true)) if v == k => None,
+            _ => Some((k.to_owned(), false)),
+        };
+    }
+
+    fn add_row(&self, columns: &[(String, ValueType)], len: usize) -> Command {
+        let row = columns
+            .iter()
+            .map(|(k, ty)| (k.clone(), ty.default_value()))
+            .collect::<Dictionary>();
+        Command::Insert {
+            path: self.item_path(len),
+            value: Value::Dictionary(row),
+        }
+    }
+
+    fn item_path(&self, i: usize) -> Vec<String> {
+        self.path
+            .iter()
+            .cloned()
+            .chain(std::iter::once(i.to_string()))
+            .collect()
+    }
+
+    /// Shows the editor for one cell, or a button to add the key if the item lacks it.
+    fn cell(
+        &self,
+        ui: &mut Ui,
+        p: &Value,
+        i: usize,
+        k: &str,
+        column_ty: ValueType,
+    ) -> Option<Command> {
+        let mut path = self.item_path(i);
+        path.push(k.to_owned());
+        let len = match try_pv(&path, p) {
+            None => None,
+            Some(Value::Array(v)) => Some(Some(v.len())),
+            Some(Value::Dictionary(v)) => Some(Some(v.len())),
+            Some(_) => Some(None),
+        };
+        match len {
+            None => ui
+                .small_button("+")
+                .on_hover_text(format!("Add {k} to this row"))
+                .clicked()
+                .then(|| Command::Insert {
+                    path,
+                    value: column_ty.default_value(),
+                }),
+            Some(Some(len)) => {
+                let s = if len == 1 { "" } else { "s" };
+                ui.add_enabled(false, Label::new(format!("{len} item{s}")));
+                None
+            }
+            Some(None) => PlistValue::new(&path, p, &self.nesting).show(ui),
+        }
+    }
+
+    fn ui(&mut self, ui: &mut Ui, p: &Value) -> Option<Command> {
+        let (columns, order) = self.layout(p);
+        let mut ret = Vec::new();
+
+        ui.horizontal(|ui| {
+            if ui.button("Add Row").clicked() {
+                ret.push(self.add_row(&columns, order.len()));
+            }
+            ui.menu_button("Columns", |ui| {
+                for (k, _) in &columns {
+                    let mut shown = !self.hidden.contains(k);
+                    if ui.checkbox(&mut shown, k).changed() {
+                        if shown {
+                            self.hidden.retain(|v| v != k);
+                        } else {
+                            self.hidden.push(k.clone());
+                        }
+                    }
+                }
+            });
+            let s = if order.len() == 1 { "" } else { "s" };
+            ui.label(format!("{} row{s}", order.len()));
+        });
+        ui.separator();
+
+        let visible = columns
+            .iter()
+            .filter(|(k, _)| !self.hidden.contains(k))
+            .collect::<Vec<_>>();
+        let mut remove = None;
+        TableBuilder::new(ui)
+            .id_salt("table_view")
+            .striped(true)
+            .resizable(true)
+            .cell_layout(Layout::left_to_right(Align::Center))
+            .column(Column::auto())
+            .columns(
+                Column::initial(160.0).at_least(60.0).clip(true),
+                visible.len(),
+            )
+            .auto_shrink([false, false])
+            .header(20.0, |mut header| {
+                header.col(|ui| {
+                    ui.strong("#");
+                });
+                for (k, ty) in &visible {
+                    header.col(|ui| {
+                        let arrow = match &self.sort {
+                            Some((v, false)) if v == k => " ⏶",
+                            Some((v, true)) if v == k => " ⏷",
+                            _ => "",
+                        };
+                        if ui
+                            .add(
+                                Label::new(RichText::new(format!("{k}{arrow}")).strong())
+                                    .sense(Sense::click()),
+                            )
+                            .on_hover_text(format!("{ty:?}. Click to sort"))
+                            .clicked()
+                        {
+                            self.toggle_sort(k);
+                        }
+                    });
+                }
+            })
+            .body(|body| {
+                body.rows(ROW_HEIGHT, order.len(), |mut row| {
+                    let i = order[row.index()];
+                    row.col(|ui| {
+                        if ui.small_button("✕").on_hover_text("Remove row").clicked() {
+                            remove = Some(i);
+                        }
+                        ui.label(i.to_string());
+                    });
+                    for (k, ty) in &visible {
+                        row.col(|ui| ret.extend(self.cell(ui, p, i, k, *ty)));
+                    }
+                });
+            });
+        if let Some(i) = remove {
+            ret.push(Command::Remove {
+                path: self.item_path(i),
+            });
+        }
+        Command::batch(ret)
+    }
+}
@@ -0,0 +1,232 @@
+//! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
+//! See LICENSE for details.
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use egui::{Context, Id};
+use egui_extras::TableBody;
+use plist::Value;
+
+use super::{
+    entry::{self, PlistEntry},
+    multiline,
+    nested::{self, Nested},
+};
+use crate::{
+    document::{Command, Document},
+    selection::Selection,
+};
+
+fn stale_id() -> Id {
+    Id::new("TreeStale")
+}
+
+/// Marks the rows as out of date from wherever a row is expanded, collapsed or resized.
+pub fn invalidate(ctx: &Context) {
+    ctx.data_mut(|d| d.insert_temp(stale_id(), true));
+}
+
+/// A document shown in the tree: the open one, or a plist nested in one of its Data values.
+struct Doc {
+    /// `None` for the open document, which is read from the [`Document`] as it is each frame.
+    value: Option<Arc<Value>>,
+    /// `None` for the open document, whose selection the app keeps.
+    selection: Option<Arc<Mutex<Selection>>>,
+    scope: Option<Id>,
+    /// The Data values a nested plist was read through, outermost first.
+    nesting: Vec<Vec<String>>,
+    /// Paths of the document's rows, in order. Shared with its selection.
+    paths: Arc<Vec<Vec<String>>>,
+}
+
+struct Row {
+    /// Index into the tree's documents.
+    doc: usize,
+    /// Index into the document's paths.
+    path: usize,
+    indent: usize,
+    /// Lines shown by an expanded string, which make the row taller.
+    lines: Option<usize>,
+}
+
+/// The rows the document is shown as, flattened so that only the ones scrolled into view are laid
+/// out.
+///
+/// They are only worked out again once something changes which rows there are: an edit, a row
+/// being expanded or collapsed, or the filter.
+#[derive(Default)]
+pub struct Tree {
+    docs: Vec<Doc>,
+    rows: Vec<Row>,
+    /// Whether the filter bar is showing matches, which are always shown expanded.
+    filtering: bool,
+    fresh: bool,
+    /// The document revision the rows were worked out for.
+    revision: u64,
+}
+
+impl Tree {
+    /// Marks the rows as out of date, after the document or the filter changed.
+    pub const fn invalidate(&mut self) {
+        self.fresh = false;
+    }
+
+    /// Where the row for `path` in the open document is laid out, counting rows from the top.
+    #[must_use]
+    pub fn row_index(&self, path: &[String]) -> Option<usize> {
+        let i = self.docs.first()?.paths.iter().position(|v| v == path)?;
+        self.rows.iter().position(|v| v.doc == 0 && v.path == i)
+    }
+
+    /// Works the rows out again if they are out of date, and gives the selections their order.
+    pub fn refresh(
+        &mut self,
+        ctx: &Context,
+        document: &Document,
+        filter: Option<&HashSet<Vec<String>>>,
+        selection: &mut Selection,
+    ) {
+        let requested = ctx.data_mut(|d| d.remove_temp::<bool>(stale_id()).is_some());
+        if self.fresh && !requested && self.revision == document.revision() {
+            return;
+        }
+        self.fresh = true;
+        self.revision = document.revision();
+        self.filtering = filter.is_some();
+        self.rows.clear();
+        self.docs = vec![Doc {
+            value: None,
+            selection: None,
+            scope: None,
+            nesting: Vec::new(),
+            paths: Arc::default(),
+        }];
+        self.push(ctx, 0, document.root(), &mut Vec::new(), 0, filter);
+        for doc in &self.docs {
+            match &doc.selection {
+                Some(v) => v.lock().unwrap().set_rows(doc.paths.clone()),
+                None => selection.set_rows(doc.paths.clone()),
+            }
+        }
+    }
+
+    /// Adds the row for `v` and, if it's expanded or being filtered, the rows below it.
+    fn push(
+        &mut self,
+        ctx: &Context,
+        doc: usize,
+        v: &Value,
+        path: &mut Vec<String>,
+        depth: usize,
+        filter: Option<&HashSet<Vec<String>>>,
+    ) {
+        if filter.is_some_and(|v| !v.contains(path.as_slice())) {
+            return;
+        }
+        let scope = self.docs[doc].scope;
+        let expanded = entry::is_expanded_in(ctx, scope, path);
+        let paths = Arc::make_mut(&mut self.docs[doc].paths);
+        self.rows.push(Row {
+            doc,
+            path: paths.len(),
+            indent: depth + path.len(),
+            lines: multiline::expanded_lines(ctx, scope, path, v),
+        });
+        paths.push(path.clone());
+        if expanded && let Value::Data(bytes) = v {
+            self.push_nested(ctx, doc, bytes, path, depth);
+        }
+        if !expanded && filter.is_none() {
+            return;
+        }
+        match v {
+            Value::Dictionary(v) => {
+                for (k, v) in v {
+                    path.push(k.clone());
+                    self.push(ctx, doc, v, path, depth, filter);
+                    path.pop();
+                }
+            }
+            Value::Array(v) => {
+                for (i, v) in v.iter().enumerate() {
+                    path.push(i.to_string());
+                    self.push(ctx, doc, v, path, depth, filter);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Adds the rows of the plist held in the Data value at `path`, as a document of its own.
+    fn push_nested(
+        &mut self,
+        ctx: &Context,
+        parent: usize,
+        bytes: &[u8],
+        path: &[String],
+        depth: usize,
+    ) {
+        let mut nesting = self.docs[parent].nesting.clone();
+        nesting.push(path.to_vec());
+        let scope = nested::scope(&nesting);
+        let Some(nested) = scope.and_then(|v| Nested::load(ctx, v, bytes)) else {
+            return;
+        };
+        let doc = self.docs.len();
+        self.docs.push(Doc {
+            value: Some(nested.value.clone()),
+            selection: Some(nested.selection),
+            scope,
+            nesting,
+            paths: Arc::default(),
+        });
+        self.push(
+            ctx,
+            doc,
+            &nested.value,
+            &mut Vec::new(),
+            depth + path.len() + 1,
+            None,
+        );
+    }
+
+    /// Lays out the rows scrolled into view, returning the edits made in them.
+    pub fn show(
+        &self,
+        mut body: TableBody,
+        root: &Value,
+        selection: &mut Selection,
+    ) -> Vec<Command> {
+        let height = multiline::row_height(body.ui_mut());
+        let mut ret = Vec::new();
+        body.heterogeneous_rows(self.rows.iter().map(|v| height(v.lines)), |mut row| {
+            let Row {
+                doc, path, indent, ..
+            } = &self.rows[row.index()];
+            let doc = &self.docs[*doc];
+            let path = doc.paths[*path].clone();
+            let mut nested = doc.selection.as_ref().map(|v| v.lock().unwrap());
+            let commands = PlistEntry::new(
+                doc.value.as_deref().unwrap_or(root),
+                path,
+                doc.scope,
+                &doc.nesting,
+                *indent,
+                self.filtering && doc.nesting.is_empty(),
+                nested.as_deref_mut().unwrap_or(&mut *selection),
+            )
+            .show(&mut row);
+            drop(nested);
+            ret.extend(
+                commands
+                    .into_iter()
+                    .map(|v| Command::nested(&doc.nesting, v)),
+            );
+        });
+        ret
+    }
+}
@@ -1,115 +1,310 @@
 //! Copyright © 2022-2025 ChefKiss Inc. Licensed under the Thou Shalt Not Profit License version 1.5.
 //! See LICENSE for details.
 
-use std::sync::{Arc, Mutex};
-
-use egui::{DragValue, Ui};
+use egui::{Align, CursorIcon, Label, Layout, Sense, Ui};
 use plist::Value;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    click_text_edit::ClickableTextEdit, date::DateEdit, entry, hex_editor, image_preview,
+    multiline, nested, toggle::Toggle,
+};
+use crate::{
+    document::Command,
+    utils::{format_real, int_to_i128, parse_integer, parse_real, pv},
+};
 
-use super::{click_text_edit::ClickableTextEdit, toggle::Toggle};
-use crate::utils::{ValueType, pv, pv_mut};
+/// How an integer row is displayed. Input is accepted in any radix regardless.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Radix {
+    #[default]
+    Decimal,
+    Hexadecimal,
+    Binary,
+}
+
+impl Radix {
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Decimal => "Dec",
+            Self::Hexadecimal => "Hex",
+            Self::Binary => "Bin",
+        }
+    }
+
+    #[must_use]
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Decimal => Self::Hexadecimal,
+            Self::Hexadecimal => Self::Binary,
+            Self::Binary => Self::Decimal,
+        }
+    }
+
+    #[must_use]
+    pub fn format(self, v: i128) -> String {
+        let sign = if v < 0 { "-" } else { "" };
+        let abs = v.unsigned_abs();
+        match self {
+            Self::Decimal => v.to_string(),
+            Self::Hexadecimal => format!("{sign}0x{abs:X}"),
+            Self::Binary => format!("{sign}0b{abs:b}"),
+        }
+    }
+}
 
 pub struct PlistValue<'a> {
     path: &'a [String],
-    data: Arc<Mutex<Value>>,
+    data: &'a Value,
+    /// The Data values leading to the document, if it's a nested one.
+    nesting: &'a [Vec<String>],
 }
 
 impl<'a> PlistValue<'a> {
     #[must_use]
-    pub const fn new(path: &'a [String], data: Arc<Mutex<Value>>) -> Self {
-        Self { path, data }
+    pub const fn new(path: &'a [String], data: &'a Value, nesting: &'a [Vec<String>]) -> Self {
+        Self {
+            path,
+            data,
+            nesting,
+        }
     }
 
+    /// Shows the value's editor, returning the edit made to it.
     #[must_use]
-    pub fn show(self, ui: &mut Ui) -> bool {
-        let Self { path, data } = self;
-
-        let ty = ValueType::from_val(path, &data.lock().unwrap());
-        let mut data = data.lock().unwrap();
-        match ty {
-            ValueType::String => {
-                let Value::String(s) = pv_mut(path, &mut data) else {
-                    unreachable!();
-                };
-                let mut changed = false;
-                ui.add(ClickableTextEdit::from_get_set(
-                    |v| {
-                        if let Some(v) = v {
-                            *s = v;
-                            changed = true;
+    pub fn show(self, ui: &mut Ui) -> Option<Command> {
+        let Self {
+            path,
+            data,
+            nesting,
+        } = self;
+        let scope = nested::scope(nesting);
+
+        let mut new = None;
+        match pv(path, data) {
+            Value::String(s) => {
+                let lines = multiline::line_count(s);
+                let mut expanded = multiline::is_expanded(ui.ctx(), scope, path);
+                ui.with_layout(Layout::right_to_left(Align::Min), |ui| {
+                    if ui
+                        .small_button("⛶")
+                        .on_hover_text("Open in a larger editor")
+                        .clicked()
+                    {
+                        multiline::open(ui.ctx(), nesting, path, s);
+                    }
+                    let toggle = if lines > 1 {
+                        format!("{} {lines} lines", if expanded { "⏶" } else { "⏷" })
+                    } else if expanded {
+                        "⏶".to_owned()
+                    } else {
+                        "⏷".to_owned()
+                    };
+                    if ui
+                        .small_button(toggle)
+                        .on_hover_text("Edit over several lines")
+                        .clicked()
+                    {
+                        expanded = !expanded;
+                        multiline::set_expanded(ui.ctx(), scope, path, expanded);
+                    }
+
+                    ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
+                        if expanded {
+                            let mut s = s.clone();
+                            if multiline::show_expanded(ui, &mut s).changed() {
+                                new = Some(Value::String(s));
+                            }
+                        } else if lines > 1 {
+                            let response = ui
+                                .add(
+                                    Label::new(multiline::visible_whitespace(s))
+                                        .truncate()
+                                        .sense(Sense::click()),
+                                )
+                                .on_hover_text("Double-click to edit");
+                            if response.double_clicked() {
+                                multiline::set_expanded(ui.ctx(), scope, path, true);
+                            }
+                        } else {
+                            ui.add(ClickableTextEdit::from_get_set(
+                                |v| {
+                                    if let Some(v) = v {
+                                        new = Some(Value::String(v));
+                                    }
+                                    s.clone()
+                                },
+                                |_| true,
+                                false,
+                            ));
                         }
-                        s.clone()
-                    },
-                    |_| true,
-                    false,
-                ));
-                changed
+                    });
+                });
             }
-            ValueType::Integer => {
-                let Some(i) = pv(path, &data).as_signed_integer().map(|v| v.to_string()) else {
-                    unreachable!();
-                };
-                let mut changed = false;
-                ui.add(ClickableTextEdit::from_get_set(
-                    |v| {
-                        if let Some(val) = &v
-                            && let Ok(val) = val.parse::<i64>()
-                        {
-                            *pv_mut(path, &mut data) = Value::Integer(val.into());
-                            changed = true;
-                        }
-                        v.unwrap_or_else(|| i.clone())
-                    },
-                    |v| v.parse::<i64>().is_ok(),
-                    false,
-                ));
-                changed
+            Value::Integer(i) => {
+                let i = int_to_i128(*i);
+                let radix_id = entry::row_id(scope, path).with("radix");
+                let mut radix = ui
+                    .data_mut(|d| d.get_persisted::<Radix>(radix_id))
+                    .unwrap_or_default();
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    if ui
+                        .small_button(radix.label())
+                        .on_hover_text("Change the displayed radix")
+                        .clicked()
+                    {
+                        radix = radix.next();
+                        ui.data_mut(|d| d.insert_persisted(radix_id, radix));
+                    }
+                    ui.add(ClickableTextEdit::from_get_set(
+                        |v| {
+                            if let Some(val) = &v
+                                && let Some(val) = parse_integer(val)
+                            {
+                                new = Some(Value::Integer(val));
+                            }
+                            v.unwrap_or_else(|| radix.format(i))
+                        },
+                        |v| parse_integer(v).is_some(),
+                        false,
+                    ))
+                    .on_hover_text(format!(
+                        "{}\n{}\n{}",
+                        Radix::Decimal.format(i),
+                        Radix::Hexadecimal.format(i),
+                        Radix::Binary.format(i)
+                    ));
+                });
             }
-            ValueType::Real => {
-                let Value::Real(value) = pv_mut(path, &mut data) else {
-                    unreachable!();
-                };
-                let mut changed = false;
-                ui.add(DragValue::from_get_set(|v: Option<f64>| {
-                    if let Some(v) = v {
-                        *value = v;
-                        changed = true;
+            Value::Real(value) => {
+                let mut value = *value;
+                let response = ui
+                    .add(ClickableTextEdit::from_get_set(
+                        |v| {
+                            if let Some(v) = &v
+                                && let Some(v) = parse_real(v)
+                            {
+                                value = v;
+                                new = Some(Value::Real(v));
+                            }
+                            format_real(value)
+                        },
+                        |v| parse_real(v).is_some(),
+                        false,
+                    ))
+                    .on_hover_text("Alt-drag to adjust");
+                if ui.input(|i| i.modifiers.alt) && value.is_finite() {
+                    let response = response.on_hover_cursor(CursorIcon::ResizeHorizontal);
+                    if response.dragged() {
+                        let speed = (value.abs() * 0.01).max(0.01);
+                        value += f64::from(response.drag_delta().x) * speed;
+                        new = Some(Value::Real(value));
                     }
-                    *value
-                }));
-                changed
+                }
             }
-            ValueType::Boolean => {
-                let Value::Boolean(v) = pv_mut(path, &mut data) else {
-                    unreachable!();
-                };
-                ui.add(Toggle::new(v)).clicked()
+            Value::Boolean(v) => {
+                let mut v = *v;
+                if ui.add(Toggle::new(&mut v)).clicked() {
+                    new = Some(Value::Boolean(v));
+                }
             }
-            ValueType::Data => {
-                let Some(val) = pv(path, &data).as_data() else {
-                    unreachable!();
-                };
-                let val = hex::encode_upper(val);
-                let mut changed = false;
-                ui.add(ClickableTextEdit::from_get_set(
-                    |v| {
-                        if let Some(val) = &v
-                            && let Ok(val) = hex::decode(val)
-                        {
-                            *pv_mut(path, &mut data) = Value::Data(val);
-                            changed = true;
-                        }
-                        v.unwrap_or_else(|| val.clone())
-                    },
-                    |v| v.len().is_multiple_of(2) && hex::decode(v).is_ok(),
-                    false,
-                ));
-                changed
+            Value::Data(bytes) => {
+                let val = hex::encode_upper(bytes);
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    if ui
+                        .small_button("Edit")
+                        .on_hover_text("Open in the hex editor")
+                        .clicked()
+                    {
+                        hex_editor::open(ui.ctx(), nesting, path);
+                    }
+                    image_preview::thumbnail(ui, scope, path, bytes);
+                    ui.add(ClickableTextEdit::from_get_set(
+                        |v| {
+                            if let Some(val) = &v
+                                && let Ok(val) = hex::decode(val)
+                            {
+                                new = Some(Value::Data(val));
+                            }
+                            v.unwrap_or_else(|| val.clone())
+                        },
+                        |v| v.len().is_multiple_of(2) && hex::decode(v).is_ok(),
+                        false,
+                    ));
+                });
+            }
+            Value::Date(v) => {
+                let old = *v;
+                let mut v = old;
+                let id = entry::row_id(scope, path);
+                // Typing marks the field changed before anything is committed.
+                if ui.add(DateEdit::new(&mut v, id)).changed() && v != old {
+                    new = Some(Value::Date(v));
+                }
             }
             _ => {
                 ui.label("Not serialisable");
-                false
             }
         }
+        new.map(|value| Command::Set {
+            path: path.to_vec(),
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::{Context, Event, Id, Key, Modifiers, RawInput, Ui, UiBuilder};
+    use plist::Value;
+
+    use super::PlistValue;
+    use crate::{
+        document::Command,
+        widgets::{click_text_edit, date},
+    };
+
+    fn pass(ctx: &Context, root: &Value, events: Vec<Event>) -> Option<Command> {
+        ctx.begin_pass(RawInput {
+            events,
+            ..Default::default()
+        });
+        let mut ui = Ui::new(ctx.clone(), Id::new("test"), UiBuilder::new());
+        let ret = PlistValue::new(&[], root, &[]).show(&mut ui);
+        let _ = ctx.end_pass();
+        ret
+    }
+
+    fn key(key: Key, modifiers: Modifiers) -> Event {
+        Event::Key {
+            key,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers,
+        }
+    }
+
+    #[test]
+    fn typed_date() {
+        let ctx = Context::default();
+        let root = Value::Date(date::parse("2020-01-01T00:00:00Z", false).unwrap());
+        let typed = "2024-02-03T04:05:06Z";
+
+        click_text_edit::request_edit(&ctx);
+        assert_eq!(pass(&ctx, &root, vec![]), None);
+        let events = vec![
+            key(Key::A, Modifiers::COMMAND),
+            Event::Text(typed.to_owned()),
+        ];
+        assert_eq!(pass(&ctx, &root, events), None);
+        assert_eq!(
+            pass(&ctx, &root, vec![key(Key::Enter, Modifiers::NONE)]),
+            Some(Command::Set {
+                path: vec![],
+                value: Value::Date(date::parse(typed, false).unwrap()),
+            })
+        );
     }
 }